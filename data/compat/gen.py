@@ -0,0 +1,42 @@
+# Reference outputs of zlib for the `compat` module, checked by its doctests.
+# Run from this directory: python3 gen.py
+import zlib
+
+WORDS = b"lorem ipsum dolor sit amet, consectetur adipiscing elit sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.".split()
+
+
+def lcg(x):
+    return (x * 1103515245 + 12345) & 0xFFFFFFFF
+
+
+# Words and numbers, about 20000 symbols at any level, so two blocks.
+def text(n):
+    x, v = 1, bytearray()
+    while len(v) < n:
+        x = lcg(x)
+        v += WORDS[(x >> 16) % len(WORDS)] + b" %d " % (x >> 24)
+    return bytes(v[:n])
+
+
+# Incompressible, so zlib falls back to stored blocks.
+def noise(n):
+    x, v = 1, bytearray()
+    for _ in range(n):
+        x = lcg(x)
+        v.append(x >> 24)
+    return bytes(v)
+
+
+def write(name, data):
+    with open(name, "wb") as f:
+        f.write(data)
+
+
+if __name__ == "__main__":
+    assert zlib.ZLIB_RUNTIME_VERSION == "1.2.13"
+    for level in range(1, 10):
+        write("text-%d.zz" % level, zlib.compress(text(160_000), level))
+    write("noise-6.zz", zlib.compress(noise(20_000), 6))
+    for level in (1, 6, 9):
+        c = zlib.compressobj(level, zlib.DEFLATED, 31)
+        write("short-%d.gz" % level, c.compress(text(4096)) + c.flush())
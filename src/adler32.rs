@@ -20,3 +20,12 @@ impl Adler32 {
         (self.1 << 16 | self.0).to_be_bytes()
     }
 }
+// Functions.
+pub fn adler32_combine(adler1: u32, adler2: u32, len2: usize) -> u32 {
+    let rem = (len2 % BASE as usize) as u32;
+    let sum1 = adler1 & 0xFFFF;
+    let sum2 = rem * sum1 % BASE;
+    let sum1 = (sum1 + (adler2 & 0xFFFF) + BASE - 1) % BASE;
+    let sum2 = (sum2 + (adler1 >> 16) + (adler2 >> 16) + BASE - rem) % BASE;
+    sum2 << 16 | sum1
+}
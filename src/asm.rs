@@ -0,0 +1,463 @@
+//! # Assembler
+//!
+//! Build deflate streams by hand, to write test vectors no encoder produces.
+//! - [`Assembler`]: blocks, BFINAL bits, custom code lengths, stored blocks
+//!   with any LEN/NLEN, raw bits and individual tokens, valid or not.
+//! - [`assemble`]: the same from the text printed by the disassembler.
+//!
+//! Code lengths are written as given: over-subscribed or incomplete codes
+//! are accepted. A symbol needs a code in the current block to be written.
+//!
+//! The text format is one statement per line, `!` starts a comment:
+//! - `deflate`, `zlib`, `gzip`: the format, as printed by the disassembler.
+//! - `header <hex>`, `trailer <hex>`: bytes written as given before and after
+//!   the deflate stream, the wrapper of a zlib or gzip stream.
+//! - `last`: the next block is the final one.
+//! - `stored`, `fixed`, `dynamic`, `reserved`: start a block.
+//! - `count <lcount> <dcount> <wcount>`, `code <symbol> <width>`,
+//!   `litlen <symbol> <width>`, `dist <symbol> <width>`: code lengths of a
+//!   dynamic block. Counts and the code length code are computed if missing.
+//! - `len <len> <nlen>`: LEN and NLEN of a stored block.
+//! - `literal '<text>`, `literal <byte>`, `data ...`: literals, stored data.
+//! - `match <length> <distance>`, `end`: pointers, end of block.
+//! - `symbol <symbol>`, `dsymbol <symbol>`, `bits <width> <value>`: raw
+//!   literal/length codes, distance codes and bits.
+//!
+//! ## Examples
+//!
+//! ### Invalid stream.
+//! ```
+//! use devker::asm::{assemble, Assembler};
+//! use devker::prelude::{inflate, Cache};
+//!
+//! let mut cache = Cache::new();
+//! let mut assembler = Assembler::new();
+//! assembler.fixed(true);
+//! assembler.literal(b'a').unwrap();
+//! assembler.pointer(3, 2).unwrap();
+//! assembler.end().unwrap();
+//! let encoded = assembler.finish();
+//!
+//! assert!(inflate(&encoded, &mut cache).is_err());
+//!
+//! // Only what a stream can hold: 32 bits at once, code lengths up to 15
+//! // and code length code lengths up to 7.
+//! assert!(Assembler::new().bits(0, 40).is_err());
+//! assert!(Assembler::new().dynamic(true, &[20; 257], &[1]).is_err());
+//! assert!(assemble("bits 40 0\n").is_err());
+//! assert!(assemble("dynamic\nlitlen 0 16\n").is_err());
+//! assert!(assemble("dynamic\ncode 0 8\n").is_err());
+//! ```
+//!
+//! ### Text.
+//! ```
+//! use devker::asm::assemble;
+//! use devker::prelude::{
+//!     deflate, disassemble, inflate, zlib_encode, BlockType, Cache, Format, InflateOptions,
+//! };
+//!
+//! let mut cache = Cache::new();
+//! let options = InflateOptions::default();
+//! let encoded = assemble(
+//!     "stored\n\
+//!      data 'Hello\n\
+//!      end\n\
+//!      last\n\
+//!      fixed\n\
+//!      literal 32\n\
+//!      match 5 6\n\
+//!      end\n",
+//! )
+//! .unwrap();
+//! assert_eq!(inflate(&encoded, &mut cache).unwrap(), b"Hello Hello");
+//!
+//! // Disassembled streams are assembled back.
+//! let v_in = "Hello world ! ".repeat(4).into_bytes();
+//! let encoded = deflate(&v_in, BlockType::Dynamic, &mut cache);
+//! let text = disassemble(&encoded, Format::Deflate, true, options, &mut cache)
+//!     .unwrap()
+//!     .to_string();
+//! assert_eq!(assemble(&text).unwrap(), encoded);
+//!
+//! // And so are zlib and gzip streams, with their header and trailer.
+//! let encoded = zlib_encode(&v_in, BlockType::Fixed, &mut cache);
+//! let text = disassemble(&encoded, Format::Zlib, true, options, &mut cache)
+//!     .unwrap()
+//!     .to_string();
+//! assert!(text.starts_with("zlib\nheader 78 9c\n"));
+//! assert_eq!(assemble(&text).unwrap(), encoded);
+//! ```
+//!
+//! [`Assembler`]: struct.Assembler.html
+//! [`assemble`]: fn.assemble.html
+
+// Imports.
+use crate::bits::{BitWriter, Bits};
+use crate::huffman::{fixed_widthes, width_code_widthes, width_codes, write_width_codes, Encoder};
+use crate::prelude::{BlockType, Code};
+use std::convert::TryFrom;
+// Constants.
+const ERROR_COUNT: &str = "Invalid code counts.";
+const ERROR_DISTANCE: &str = "Invalid distance.";
+const ERROR_LENGTH: &str = "Invalid length.";
+const ERROR_STATEMENT: &str = "Invalid statement";
+const ERROR_SYMBOL: &str = "Symbol without code in the current block.";
+const ERROR_WIDTH: &str = "Invalid width.";
+const MAX_BITS: u8 = 32;
+const MAX_WIDTH: u8 = 15;
+const MAX_WIDTH_WIDTH: u8 = 7;
+const RESERVED: u8 = 0b11;
+// Structures.
+#[derive(Debug, Clone)]
+pub struct Assembler {
+    writer: BitWriter,
+    lwidthes: Vec<u8>,
+    dwidthes: Vec<u8>,
+    literal: Encoder,
+    distance: Encoder,
+}
+enum Statement {
+    None,
+    Stored(bool, Option<(u16, u16)>, Vec<u8>),
+    Dynamic(
+        bool,
+        Option<(usize, usize, usize)>,
+        Option<[u8; 19]>,
+        Vec<u8>,
+        Vec<u8>,
+    ),
+}
+// Implementations.
+impl Assembler {
+    pub fn new() -> Self {
+        Self {
+            writer: BitWriter::new(),
+            lwidthes: Vec::new(),
+            dwidthes: Vec::new(),
+            literal: Encoder::unchecked(&[]),
+            distance: Encoder::unchecked(&[]),
+        }
+    }
+    fn header(&mut self, bfinal: bool, btype: u8) {
+        self.writer.write(bfinal as u32, 1);
+        self.writer.write(btype as u32, 2);
+    }
+    fn set_widthes(&mut self, lwidthes: &[u8], dwidthes: &[u8]) {
+        self.literal = Encoder::unchecked(lwidthes);
+        self.distance = Encoder::unchecked(dwidthes);
+        self.lwidthes = lwidthes.to_vec();
+        self.dwidthes = dwidthes.to_vec();
+    }
+    pub fn bits(&mut self, data: u32, width: u8) -> Result<(), String> {
+        if width > MAX_BITS {
+            return Err(ERROR_WIDTH.into());
+        }
+        self.writer.write(data, width);
+        Ok(())
+    }
+    pub fn stored(&mut self, bfinal: bool, data: &[u8]) -> Result<(), String> {
+        let len = match u16::try_from(data.len()) {
+            Ok(len) => len,
+            Err(_) => return Err(ERROR_LENGTH.into()),
+        };
+        self.stored_with(bfinal, len, !len, data);
+        Ok(())
+    }
+    pub fn stored_with(&mut self, bfinal: bool, len: u16, nlen: u16, data: &[u8]) {
+        self.header(bfinal, BlockType::Raw as u8);
+        self.writer.align();
+        self.writer.write(len as u32, 16);
+        self.writer.write(nlen as u32, 16);
+        self.writer.write_bytes(data);
+    }
+    pub fn fixed(&mut self, bfinal: bool) {
+        let (lwidthes, dwidthes) = fixed_widthes();
+        self.header(bfinal, BlockType::Fixed as u8);
+        self.set_widthes(&lwidthes, &dwidthes);
+    }
+    pub fn dynamic(
+        &mut self,
+        bfinal: bool,
+        lwidthes: &[u8],
+        dwidthes: &[u8],
+    ) -> Result<(), String> {
+        check_widthes(lwidthes, dwidthes)?;
+        let codes = width_codes(&[lwidthes, dwidthes].concat());
+        let (wwidthes, wcount) = width_code_widthes(&codes);
+        self.dynamic_with(bfinal, &wwidthes, wcount, lwidthes, dwidthes)
+    }
+    pub fn dynamic_with(
+        &mut self,
+        bfinal: bool,
+        wwidthes: &[u8; 19],
+        wcount: usize,
+        lwidthes: &[u8],
+        dwidthes: &[u8],
+    ) -> Result<(), String> {
+        let (lcount, dcount) = (lwidthes.len(), dwidthes.len());
+        if !(257..=288).contains(&lcount) || !(1..=32).contains(&dcount) {
+            return Err(ERROR_COUNT.into());
+        }
+        if !(4..=19).contains(&wcount) || wwidthes.iter().any(|x| *x > MAX_WIDTH_WIDTH) {
+            return Err(ERROR_COUNT.into());
+        }
+        check_widthes(lwidthes, dwidthes)?;
+        let codes = width_codes(&[lwidthes, dwidthes].concat());
+        if codes.iter().any(|(code, _)| wwidthes[*code as usize] == 0) {
+            return Err(ERROR_SYMBOL.into());
+        }
+        self.header(bfinal, BlockType::Dynamic as u8);
+        write_width_codes(&mut self.writer, wwidthes, wcount, lcount, dcount, &codes);
+        self.set_widthes(lwidthes, dwidthes);
+        Ok(())
+    }
+    pub fn reserved(&mut self, bfinal: bool) {
+        self.header(bfinal, RESERVED);
+    }
+    pub fn symbol(&mut self, symbol: u16) -> Result<(), String> {
+        match self.lwidthes.get(symbol as usize) {
+            Some(width) if *width > 0 => self.literal.encode(&mut self.writer, symbol as usize),
+            _ => return Err(ERROR_SYMBOL.into()),
+        }
+        Ok(())
+    }
+    pub fn distance_symbol(&mut self, symbol: u16) -> Result<(), String> {
+        match self.dwidthes.get(symbol as usize) {
+            Some(width) if *width > 0 => self.distance.encode(&mut self.writer, symbol as usize),
+            _ => return Err(ERROR_SYMBOL.into()),
+        }
+        Ok(())
+    }
+    pub fn literal(&mut self, literal: u8) -> Result<(), String> {
+        self.symbol(literal as u16)
+    }
+    pub fn pointer(&mut self, length: u16, distance: u16) -> Result<(), String> {
+        if !(3..=258).contains(&length) {
+            return Err(ERROR_LENGTH.into());
+        }
+        if !(1..=0x8000).contains(&distance) {
+            return Err(ERROR_DISTANCE.into());
+        }
+        let code = Code::Pointer {
+            length: (length - 3) as u8,
+            distance,
+        };
+        self.symbol(code.literal_code())?;
+        if let Some((width, data)) = code.extra_length() {
+            self.writer.write_bits(Bits { data, width });
+        }
+        if let Some((symbol, width, data)) = code.distance_code() {
+            self.distance_symbol(symbol)?;
+            self.writer.write_bits(Bits { data, width });
+        }
+        Ok(())
+    }
+    pub fn end(&mut self) -> Result<(), String> {
+        self.symbol(Code::EndOfBlock.literal_code())
+    }
+    pub fn position(&self) -> usize {
+        self.writer.position()
+    }
+    pub fn finish(self) -> Vec<u8> {
+        self.writer.finish()
+    }
+}
+impl Default for Assembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+// Functions.
+fn parse<T: std::str::FromStr>(arg: Option<&str>) -> Result<T, String> {
+    match arg.map(|x| x.parse()) {
+        Some(Ok(x)) => Ok(x),
+        _ => Err(ERROR_STATEMENT.into()),
+    }
+}
+fn parse_literals(rest: &str) -> Result<Vec<u8>, String> {
+    match rest.strip_prefix('\'') {
+        Some(text) => Ok(text.as_bytes().to_vec()),
+        None => rest.split_whitespace().map(|x| parse(Some(x))).collect(),
+    }
+}
+fn check_widthes(lwidthes: &[u8], dwidthes: &[u8]) -> Result<(), String> {
+    // Widthes 16 to 18 would be written as the repeat codes.
+    match lwidthes.iter().chain(dwidthes).any(|x| *x > MAX_WIDTH) {
+        true => Err(ERROR_WIDTH.into()),
+        false => Ok(()),
+    }
+}
+fn set_width(widthes: &mut Vec<u8>, symbol: usize, width: u8) -> Result<(), String> {
+    if width > MAX_WIDTH {
+        return Err(ERROR_WIDTH.into());
+    }
+    if widthes.len() <= symbol {
+        widthes.resize(symbol + 1, 0);
+    }
+    widthes[symbol] = width;
+    Ok(())
+}
+fn flush(assembler: &mut Assembler, statement: &mut Statement) -> Result<(), String> {
+    if let Statement::Dynamic(bfinal, counts, wwidthes, lwidthes, dwidthes) = statement {
+        let (lcount, dcount) = match counts {
+            Some((lcount, dcount, _)) => (*lcount, *dcount),
+            None => (lwidthes.len().max(257), dwidthes.len().max(1)),
+        };
+        if lwidthes.len() > lcount || dwidthes.len() > dcount {
+            return Err(ERROR_COUNT.into());
+        }
+        lwidthes.resize(lcount, 0);
+        dwidthes.resize(dcount, 0);
+        match (wwidthes, counts) {
+            (Some(wwidthes), Some((_, _, wcount))) => {
+                assembler.dynamic_with(*bfinal, wwidthes, *wcount, lwidthes, dwidthes)?
+            }
+            (None, _) => assembler.dynamic(*bfinal, lwidthes, dwidthes)?,
+            _ => return Err(ERROR_COUNT.into()),
+        }
+        *statement = Statement::None;
+    }
+    Ok(())
+}
+fn parse_hex(rest: &str) -> Result<Vec<u8>, String> {
+    rest.split_whitespace()
+        .map(|x| u8::from_str_radix(x, 16).map_err(|_| ERROR_STATEMENT.into()))
+        .collect()
+}
+fn assemble_line(
+    assembler: &mut Assembler,
+    statement: &mut Statement,
+    bfinal: &mut bool,
+    wrapper: &mut (Vec<u8>, Vec<u8>),
+    line: &str,
+) -> Result<(), String> {
+    let (keyword, rest) = match line.split_once(' ') {
+        Some((keyword, rest)) => (keyword, rest),
+        None => (line, ""),
+    };
+    let mut args = rest.split_whitespace();
+
+    // Statements inside a block header.
+    match (keyword, &mut *statement) {
+        ("count", Statement::Dynamic(_, counts, ..)) => {
+            *counts = Some((
+                parse(args.next())?,
+                parse(args.next())?,
+                parse(args.next())?,
+            ));
+            return Ok(());
+        }
+        ("code", Statement::Dynamic(_, _, wwidthes, ..)) => {
+            let symbol: usize = parse(args.next())?;
+            let wwidthes = wwidthes.get_or_insert([0; 19]);
+            let width = parse(args.next())?;
+            if width > MAX_WIDTH_WIDTH {
+                return Err(ERROR_WIDTH.into());
+            }
+            match wwidthes.get_mut(symbol) {
+                Some(x) => *x = width,
+                None => return Err(ERROR_SYMBOL.into()),
+            }
+            return Ok(());
+        }
+        ("litlen", Statement::Dynamic(_, _, _, lwidthes, _)) => {
+            return set_width(lwidthes, parse(args.next())?, parse(args.next())?);
+        }
+        ("dist", Statement::Dynamic(_, _, _, _, dwidthes)) => {
+            return set_width(dwidthes, parse(args.next())?, parse(args.next())?);
+        }
+        ("len", Statement::Stored(_, len, _)) => {
+            *len = Some((parse(args.next())?, parse(args.next())?));
+            return Ok(());
+        }
+        ("data", Statement::Stored(_, _, data)) => {
+            data.extend(parse_literals(rest)?);
+            return Ok(());
+        }
+        ("end", Statement::Stored(bfinal, len, data)) => {
+            match len {
+                Some((len, nlen)) => assembler.stored_with(*bfinal, *len, *nlen, data),
+                None => assembler.stored(*bfinal, data)?,
+            }
+            *statement = Statement::None;
+            return Ok(());
+        }
+        (_, Statement::Stored(..)) => return Err(ERROR_STATEMENT.into()),
+        _ => flush(assembler, statement)?,
+    }
+
+    // Statements between or inside blocks.
+    match keyword {
+        "deflate" | "zlib" | "gzip" => return Ok(()),
+        "header" => wrapper.0.extend(parse_hex(rest)?),
+        "trailer" => wrapper.1.extend(parse_hex(rest)?),
+        "last" => *bfinal = true,
+        "stored" => *statement = Statement::Stored(*bfinal, None, Vec::new()),
+        "fixed" => {
+            assembler.fixed(*bfinal);
+            *statement = Statement::None;
+        }
+        "dynamic" => {
+            *statement = Statement::Dynamic(*bfinal, None, None, Vec::new(), Vec::new());
+        }
+        "reserved" => {
+            assembler.reserved(*bfinal);
+            *statement = Statement::None;
+        }
+        "bits" => {
+            let width = parse(args.next())?;
+            if width > MAX_BITS {
+                return Err(ERROR_WIDTH.into());
+            }
+            assembler.bits(parse(args.next())?, width)?;
+        }
+        "literal" => {
+            for x in parse_literals(rest)? {
+                assembler.literal(x)?;
+            }
+        }
+        "match" => assembler.pointer(parse(args.next())?, parse(args.next())?)?,
+        "symbol" => assembler.symbol(parse(args.next())?)?,
+        "dsymbol" => assembler.distance_symbol(parse(args.next())?)?,
+        "end" => {
+            assembler.end()?;
+            *statement = Statement::None;
+        }
+        _ => return Err(ERROR_STATEMENT.into()),
+    }
+    if !matches!(keyword, "last" | "bits" | "header" | "trailer") {
+        *bfinal = false;
+    }
+    Ok(())
+}
+// Main functions.
+pub fn assemble(text: &str) -> Result<Vec<u8>, String> {
+    // Variable initialization.
+    let mut assembler = Assembler::new();
+    let mut statement = Statement::None;
+    let mut bfinal = false;
+    let mut wrapper = (Vec::new(), Vec::new());
+
+    // Algorithm.
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim_start();
+        if line.is_empty() || line.starts_with('!') {
+            continue;
+        }
+        let result = assemble_line(
+            &mut assembler,
+            &mut statement,
+            &mut bfinal,
+            &mut wrapper,
+            line,
+        );
+        if let Err(error) = result {
+            return Err(format!("{} (line {})", error, i + 1));
+        }
+    }
+    flush(&mut assembler, &mut statement)?;
+    let (mut v_out, trailer) = wrapper;
+    v_out.extend(assembler.finish());
+    v_out.extend(trailer);
+    Ok(v_out)
+}
@@ -1,25 +1,420 @@
 //! # Bits
+//!
+//! Variable-width codes, bit readers and bit writers.
+//! - Bits: `width` bits stored in the low bits of `data`.
+//! - BitReader/BitWriter: LSB-first, as in deflate.
+//! - MsbBitReader/MsbBitWriter: MSB-first.
+//!
+//! Readers peek, skip and read up to 32 bits at once, and writers write as
+//! many. Reading or skipping more is an error, peeking or writing more panics.
+//! Peeking past the end of the input gives zeros, reading or skipping past it
+//! is an error.
+//! Writers keep up to 63 pending bits and flush them 8 bytes at a time; a
+//! writer can be created with the capacity of the expected output.
+//!
+//! ## Examples
+//!
+//! ### LSB-first.
+//! ```
+//! use devker::bits::{BitReader, BitWriter};
+//!
+//! let mut writer = BitWriter::with_capacity(6);
+//! writer.write(0b101, 3);
+//! writer.write(0x1234_5678, 32);
+//! writer.align();
+//! writer.write_bytes(&[0xAB]);
+//! assert_eq!(writer.position(), 48);
+//! let encoded = writer.finish();
+//!
+//! let mut reader = BitReader::new(&encoded);
+//! assert_eq!(reader.read_bits(3), Ok(0b101));
+//! assert!(reader.read_bits(33).is_err());
+//! assert_eq!(reader.peek_bits(32), 0x1234_5678);
+//! reader.skip_bits(32).unwrap();
+//! reader.align();
+//! assert_eq!(reader.read_bytes(1), Ok(&[0xAB][..]));
+//! assert!(reader.read_bits(1).is_err());
+//! ```
+//!
+//! ### MSB-first.
+//! ```
+//! use devker::bits::{MsbBitReader, MsbBitWriter};
+//!
+//! let mut writer = MsbBitWriter::new();
+//! writer.write(0b1, 1);
+//! writer.write(0b0000_0011, 8);
+//! let encoded = writer.finish();
+//! assert_eq!(encoded, [0b1000_0001, 0b1000_0000]);
+//!
+//! let mut reader = MsbBitReader::new(&encoded);
+//! assert_eq!(reader.read_bits(4), Ok(0b1000));
+//! assert_eq!(reader.read_bits(5), Ok(0b0_0011));
+//! assert_eq!(reader.position(), 9);
+//! ```
 
+// Constants.
+const ERROR_ALIGN: &str = "Reader is not aligned on a byte.";
+const ERROR_END: &str = "Unexpected end of input.";
+const ERROR_WIDTH: &str = "At most 32 bits are read or written at once.";
+const MAX_WIDTH: u8 = 32;
 // Structures.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Bits {
     pub data: u16,
     pub width: u8,
 }
+#[derive(Debug, Clone)]
+pub struct BitReader<'a> {
+    v_in: &'a [u8],
+    pos: usize,
+    buf: u64,
+    width: u8,
+}
+#[derive(Debug, Clone)]
+pub struct MsbBitReader<'a> {
+    v_in: &'a [u8],
+    pos: usize,
+    buf: u64,
+    width: u8,
+}
+#[derive(Debug, Clone)]
+pub struct BitWriter {
+    v_out: Vec<u8>,
+    buf: u64,
+    width: u8,
+}
+#[derive(Debug, Clone)]
+pub struct MsbBitWriter {
+    v_out: Vec<u8>,
+    buf: u64,
+    width: u8,
+}
 // Implementations.
 impl Bits {
-    pub fn from(code: i32) -> Self {
+    pub fn new(data: u16, width: u8) -> Self {
+        Self { data, width }
+    }
+    pub(crate) fn from(code: i32) -> Self {
         let width = (code >> 16) as u8;
         let data = (code & 0xFFFF) as u16;
         Self { data, width }
     }
     pub fn reverse(&self) -> Self {
         let width = self.width;
-        let data = self.data.reverse_bits() >> (16 - width);
+        let data = self.data.reverse_bits().checked_shr(16 - width as u32);
+        let data = data.unwrap_or(0);
         Self { data, width }
     }
-    pub fn as_i32(&self) -> i32 {
+    pub(crate) fn as_i32(&self) -> i32 {
         let (b0, b1) = ((self.data >> 8) as u8, (self.data & 0xFF) as u8);
         i32::from_be_bytes([0, self.width, b0, b1])
     }
 }
+impl<'a> BitReader<'a> {
+    pub fn new(v_in: &'a [u8]) -> Self {
+        Self {
+            v_in,
+            pos: 0,
+            buf: 0,
+            width: 0,
+        }
+    }
+    fn refill(&mut self) {
+        while self.width <= 56 && self.pos < self.v_in.len() {
+            self.buf |= (self.v_in[self.pos] as u64) << self.width;
+            self.width += 8;
+            self.pos += 1;
+        }
+    }
+    pub fn peek_bits(&mut self, width: u8) -> u32 {
+        assert!(width <= MAX_WIDTH, "{}", ERROR_WIDTH);
+        if self.width < width {
+            self.refill();
+        }
+        (self.buf & mask(width)) as u32
+    }
+    // Loads eight bytes at once, so that at least 56 bits are available, when they remain in the input.
+    #[inline(always)]
+    pub(crate) fn refill_fast(&mut self) -> bool {
+        match self.v_in.get(self.pos..self.pos + 8) {
+            Some(bytes) => {
+                let mut word = [0; 8];
+                word.copy_from_slice(bytes);
+                self.buf |= u64::from_le_bytes(word) << self.width;
+                self.pos += (63 - self.width as usize) / 8;
+                self.width |= 56;
+                true
+            }
+            None => false,
+        }
+    }
+    #[inline(always)]
+    pub(crate) fn bits(&self) -> u64 {
+        self.buf
+    }
+    #[inline(always)]
+    pub(crate) fn consume(&mut self, width: u8) {
+        self.buf >>= width;
+        self.width -= width;
+    }
+    pub fn skip_bits(&mut self, width: u8) -> Result<(), String> {
+        if width > MAX_WIDTH {
+            return Err(ERROR_WIDTH.into());
+        }
+        if self.width < width {
+            self.refill();
+            if self.width < width {
+                return Err(ERROR_END.into());
+            }
+        }
+        self.buf >>= width;
+        self.width -= width;
+        Ok(())
+    }
+    pub fn read_bits(&mut self, width: u8) -> Result<u32, String> {
+        if width > MAX_WIDTH {
+            return Err(ERROR_WIDTH.into());
+        }
+        let bits = self.peek_bits(width);
+        self.skip_bits(width)?;
+        Ok(bits)
+    }
+    pub fn align(&mut self) {
+        let width = self.width % 8;
+        self.buf >>= width;
+        self.width -= width;
+    }
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if !self.width.is_multiple_of(8) {
+            return Err(ERROR_ALIGN.into());
+        }
+        // Give back the bytes which are loaded but not used.
+        let start = self.pos - self.width as usize / 8;
+        if start + len > self.v_in.len() {
+            return Err(ERROR_END.into());
+        }
+        self.pos = start + len;
+        self.buf = 0;
+        self.width = 0;
+        Ok(&self.v_in[start..start + len])
+    }
+    pub fn position(&self) -> usize {
+        self.pos * 8 - self.width as usize
+    }
+    // Whole bytes left, the loaded ones included.
+    pub(crate) fn available(&self) -> usize {
+        self.v_in.len() - self.pos + self.width as usize / 8
+    }
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 && self.pos == self.v_in.len()
+    }
+}
+impl<'a> MsbBitReader<'a> {
+    pub fn new(v_in: &'a [u8]) -> Self {
+        Self {
+            v_in,
+            pos: 0,
+            buf: 0,
+            width: 0,
+        }
+    }
+    fn refill(&mut self) {
+        while self.width <= 56 && self.pos < self.v_in.len() {
+            self.buf = self.buf << 8 | self.v_in[self.pos] as u64;
+            self.width += 8;
+            self.pos += 1;
+        }
+    }
+    pub fn peek_bits(&mut self, width: u8) -> u32 {
+        assert!(width <= MAX_WIDTH, "{}", ERROR_WIDTH);
+        if self.width < width {
+            self.refill();
+        }
+        let bits = if self.width < width {
+            self.buf << (width - self.width)
+        } else {
+            self.buf >> (self.width - width)
+        };
+        (bits & mask(width)) as u32
+    }
+    pub fn skip_bits(&mut self, width: u8) -> Result<(), String> {
+        if width > MAX_WIDTH {
+            return Err(ERROR_WIDTH.into());
+        }
+        if self.width < width {
+            self.refill();
+            if self.width < width {
+                return Err(ERROR_END.into());
+            }
+        }
+        self.width -= width;
+        Ok(())
+    }
+    pub fn read_bits(&mut self, width: u8) -> Result<u32, String> {
+        if width > MAX_WIDTH {
+            return Err(ERROR_WIDTH.into());
+        }
+        let bits = self.peek_bits(width);
+        self.skip_bits(width)?;
+        Ok(bits)
+    }
+    pub fn align(&mut self) {
+        self.width -= self.width % 8;
+    }
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if !self.width.is_multiple_of(8) {
+            return Err(ERROR_ALIGN.into());
+        }
+        // Give back the bytes which are loaded but not used.
+        let start = self.pos - self.width as usize / 8;
+        if start + len > self.v_in.len() {
+            return Err(ERROR_END.into());
+        }
+        self.pos = start + len;
+        self.buf = 0;
+        self.width = 0;
+        Ok(&self.v_in[start..start + len])
+    }
+    pub fn position(&self) -> usize {
+        self.pos * 8 - self.width as usize
+    }
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 && self.pos == self.v_in.len()
+    }
+}
+impl BitWriter {
+    pub fn new() -> Self {
+        Self::from_vec(Vec::new())
+    }
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_vec(Vec::with_capacity(capacity))
+    }
+    pub fn from_vec(v_out: Vec<u8>) -> Self {
+        // Bits are written after the bytes already there.
+        Self {
+            v_out,
+            buf: 0,
+            width: 0,
+        }
+    }
+    pub fn reserve(&mut self, additional: usize) {
+        self.v_out.reserve(additional);
+    }
+    #[inline]
+    pub fn write(&mut self, data: u32, width: u8) {
+        assert!(width <= MAX_WIDTH, "{}", ERROR_WIDTH);
+        // Fewer than 64 bits are pending, they are flushed 8 bytes at a time.
+        let data = data as u64 & mask(width);
+        self.buf |= data << self.width;
+        if self.width + width < 64 {
+            self.width += width;
+            return;
+        }
+        // A whole word is stored in the spare capacity, which is grown only when it runs out.
+        let len = self.v_out.len();
+        if self.v_out.capacity() - len < 8 {
+            self.v_out.reserve(8);
+        }
+        unsafe {
+            let ptr = self.v_out.as_mut_ptr().add(len) as *mut u64;
+            std::ptr::write_unaligned(ptr, self.buf.to_le());
+            self.v_out.set_len(len + 8);
+        }
+        self.buf = data >> (64 - self.width);
+        self.width = self.width + width - 64;
+    }
+    #[inline]
+    pub fn write_bits(&mut self, bits: Bits) {
+        self.write(bits.data as u32, bits.width);
+    }
+    pub fn align(&mut self) {
+        let width = (8 - self.width % 8) % 8;
+        self.write(0, width);
+    }
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.align();
+        self.flush();
+        self.v_out.extend_from_slice(bytes);
+    }
+    pub fn position(&self) -> usize {
+        self.v_out.len() * 8 + self.width as usize
+    }
+    pub fn take_bytes(&mut self) -> Vec<u8> {
+        // Whole bytes only, the pending bits stay in the writer.
+        self.flush();
+        std::mem::take(&mut self.v_out)
+    }
+    pub fn finish(mut self) -> Vec<u8> {
+        self.write_bytes(&[]);
+        self.v_out
+    }
+    // Whole bytes are handed to `f` then dropped, the capacity and the pending bits stay.
+    pub(crate) fn drain_bytes<T>(&mut self, f: impl FnOnce(&[u8]) -> T) -> T {
+        self.flush();
+        let result = f(&self.v_out);
+        self.v_out.clear();
+        result
+    }
+    fn flush(&mut self) {
+        let len = self.width as usize / 8;
+        self.v_out.extend_from_slice(&self.buf.to_le_bytes()[..len]);
+        self.buf >>= 8 * len;
+        self.width -= 8 * len as u8;
+    }
+}
+impl MsbBitWriter {
+    pub fn new() -> Self {
+        Self {
+            v_out: Vec::new(),
+            buf: 0,
+            width: 0,
+        }
+    }
+    pub fn write(&mut self, data: u32, width: u8) {
+        assert!(width <= MAX_WIDTH, "{}", ERROR_WIDTH);
+        self.buf = self.buf << width | (data as u64 & mask(width));
+        self.width += width;
+
+        if self.width >= 32 {
+            self.width -= 32;
+            self.v_out
+                .extend(&((self.buf >> self.width) as u32).to_be_bytes());
+        }
+    }
+    pub fn write_bits(&mut self, bits: Bits) {
+        self.write(bits.data as u32, bits.width);
+    }
+    pub fn align(&mut self) {
+        let width = (8 - self.width % 8) % 8;
+        self.write(0, width);
+    }
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.align();
+        while self.width > 0 {
+            self.width -= 8;
+            self.v_out.push((self.buf >> self.width) as u8);
+        }
+        self.v_out.extend(bytes);
+    }
+    pub fn position(&self) -> usize {
+        self.v_out.len() * 8 + self.width as usize
+    }
+    pub fn finish(mut self) -> Vec<u8> {
+        self.write_bytes(&[]);
+        self.v_out
+    }
+}
+impl Default for BitWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Default for MsbBitWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+// Functions.
+fn mask(width: u8) -> u64 {
+    (1u64 << width) - 1
+}
@@ -1,16 +1,43 @@
 //! # Cache
 //!
-//! Memory that is used not to re-allocate,
-//! it represents 256 KB of memory which can fit into cache.
+//! Memory that is used not to re-allocate, bounded whatever the input size:
+//! - A table of 64 K entries (256 KB): the encoder's positions or the
+//!   decoder's Huffman tables.
+//! - The encoder's window: at most 64 KB of history and the block being
+//!   encoded, which is 64 KB at most.
+//! - The tokens of one block.
+//! - The output of one block of [`deflate_to`].
+//!
+//! The buffers grow up to these bounds and are kept, so compressing many
+//! small inputs with the same cache doesn't allocate once they have grown.
+//!
+//! [`deflate_to`]: ../deflate/fn.deflate_to.html
 
+// Imports.
+use crate::code::Token;
 // Structures.
-pub struct Cache([i32; 0x10000]);
+pub struct Cache {
+    pub(crate) buf: [i32; 0x10000],
+    pub(crate) window: Vec<u8>,
+    pub(crate) tokens: Vec<Token>,
+    pub(crate) bytes: Vec<u8>,
+}
 // Implementations.
 impl Cache {
     pub fn new() -> Self {
-        Self([0; 0x10000])
+        Self {
+            buf: [0; 0x10000],
+            window: Vec::new(),
+            tokens: Vec::new(),
+            bytes: Vec::new(),
+        }
     }
     pub fn inner_mut(&mut self) -> &mut [i32; 0x10000] {
-        &mut self.0
+        &mut self.buf
+    }
+}
+impl Default for Cache {
+    fn default() -> Self {
+        Self::new()
     }
 }
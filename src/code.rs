@@ -1,7 +1,79 @@
 //! # Code
+//!
+//! LZ77 tokens produced by [`lzss_encode`] and [`inflate_tokens`],
+//! and entropy-coded by [`encode_tokens`].
+//! - Literal: one byte.
+//! - Pointer: copy `length + 3` bytes starting `distance` bytes back (1..=32768).
+//! - EndOfBlock: end of the current block, skipped by [`lzss_decode`].
+//!
+//! Inside the encoder, tokens are packed into a `u32` with their symbols and
+//! extra bits already computed, from lookup tables:
+//! `symbol (9 bits) | distance symbol (5) | length extra (5) | distance extra (13)`.
+//!
+//! ## Examples
+//!
+//! ### Inspect the parse.
+//! ```
+//! use devker::prelude::{encode_tokens, inflate, inflate_tokens, lzss_decode, lzss_encode};
+//! use devker::prelude::{BlockType, Cache, Code};
+//!
+//! let mut cache = Cache::new();
+//! let v = String::from("Hello world ! ").repeat(4);
+//! let v_in = v.into_bytes();
+//!
+//! let tokens = lzss_encode(&v_in, &mut cache);
+//! let matches = tokens.iter().filter(|x| matches!(x, Code::Pointer { .. }));
+//! assert_eq!(matches.count(), 1);
+//!
+//! let encoded = encode_tokens(&tokens, BlockType::Fixed).unwrap();
+//! let decoded = inflate(&encoded, &mut cache).unwrap();
+//! assert_eq!(v_in, decoded);
+//!
+//! let mut decoded = inflate_tokens(&encoded, &mut cache).unwrap();
+//! assert_eq!(lzss_decode(&decoded).unwrap(), v_in);
+//! assert_eq!(decoded.pop(), Some(Code::EndOfBlock));
+//! assert_eq!(tokens, decoded);
+//! ```
+//!
+//! ### Supply your own parse.
+//! ```
+//! use devker::prelude::{encode_tokens, inflate, BlockType, Cache, Code};
+//!
+//! let mut cache = Cache::new();
+//! let tokens = [
+//!     Code::Literal(b'a'),
+//!     Code::Literal(b'b'),
+//!     Code::Pointer { distance: 2, length: 3 },
+//!     Code::EndOfBlock,
+//!     Code::Pointer { distance: 1, length: 0 },
+//! ];
+//!
+//! let encoded = encode_tokens(&tokens, BlockType::Raw).unwrap();
+//! let decoded = inflate(&encoded, &mut cache).unwrap();
+//! assert_eq!(decoded, b"ababababbbb");
+//!
+//! // Symbols and extra bits, a distance out of range has no code.
+//! let code = Code::Pointer { distance: 40000, length: 9 };
+//! assert_eq!(code.literal_code(), 265);
+//! assert_eq!(code.extra_length(), Some((1, 1)));
+//! assert_eq!(code.distance_code(), None);
+//! let code = Code::Pointer { distance: 5, length: 0 };
+//! assert_eq!(code.distance_code(), Some((4, 1, 0)));
+//! ```
+//!
+//! [`lzss_encode`]: ../lzss/fn.lzss_encode.html
+//! [`lzss_decode`]: ../lzss/fn.lzss_decode.html
+//! [`inflate_tokens`]: ../deflate/fn.inflate_tokens.html
+//! [`encode_tokens`]: ../deflate/fn.encode_tokens.html
 
+// Imports.
+use crate::bits::Bits;
 // Constants.
 pub(crate) const END_OF_BLOCK: u16 = 256;
+const MAX_DISTANCE: u16 = 0x8000;
+pub(crate) const WIDTH_CODE_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
 pub(crate) const LENGTH_TABLE: [(u8, u8); 29] = [
     (0, 0),
     (1, 0),
@@ -65,109 +137,147 @@ pub(crate) const DISTANCE_TABLE: [(u16, u8); 30] = [
     (16_385, 13),
     (24_577, 13),
 ];
+const LENGTH_SYMBOLS: [u8; 256] = length_symbols();
+const DISTANCE_SYMBOLS: [u8; 512] = distance_symbols();
 // Structures.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Token(u32);
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Code {
     EndOfBlock,
     Literal(u8),
     Pointer { distance: u16, length: u8 },
 }
 // Implementations.
+impl Token {
+    pub(crate) const END_OF_BLOCK: Self = Self(END_OF_BLOCK as u32);
+
+    pub(crate) fn literal(literal: u8) -> Self {
+        Self(literal as u32)
+    }
+    pub(crate) fn pointer(distance: u16, length: u8) -> Self {
+        let symbol = LENGTH_SYMBOLS[length as usize] as usize;
+        let d = distance as usize - 1;
+        let dsymbol = match d {
+            0..=255 => DISTANCE_SYMBOLS[d],
+            _ => DISTANCE_SYMBOLS[256 + (d >> 7)],
+        } as usize;
+        let extra = (length - LENGTH_TABLE[symbol].0) as u32;
+        let dextra = (distance - DISTANCE_TABLE[dsymbol].0) as u32;
+        Self((257 + symbol as u32) | (dsymbol as u32) << 9 | extra << 14 | dextra << 19)
+    }
+    pub(crate) fn symbol(self) -> usize {
+        (self.0 & 0x1FF) as usize
+    }
+    pub(crate) fn is_pointer(self) -> bool {
+        self.symbol() > END_OF_BLOCK as usize
+    }
+    pub(crate) fn distance_symbol(self) -> usize {
+        (self.0 >> 9 & 0x1F) as usize
+    }
+    pub(crate) fn length(self) -> u8 {
+        LENGTH_TABLE[self.symbol() - 257].0 + (self.0 >> 14 & 0x1F) as u8
+    }
+    pub(crate) fn distance(self) -> u16 {
+        DISTANCE_TABLE[self.distance_symbol()].0 + (self.0 >> 19) as u16
+    }
+    pub(crate) fn length_extra(self) -> Bits {
+        Bits::new(
+            (self.0 >> 14 & 0x1F) as u16,
+            LENGTH_TABLE[self.symbol() - 257].1,
+        )
+    }
+    pub(crate) fn distance_extra(self) -> Bits {
+        Bits::new(
+            (self.0 >> 19) as u16,
+            DISTANCE_TABLE[self.distance_symbol()].1,
+        )
+    }
+}
+impl From<Code> for Token {
+    fn from(code: Code) -> Self {
+        match code {
+            Code::EndOfBlock => Token::END_OF_BLOCK,
+            Code::Literal(x) => Token::literal(x),
+            Code::Pointer { distance, length } => Token::pointer(distance, length),
+        }
+    }
+}
+impl From<Token> for Code {
+    fn from(token: Token) -> Self {
+        match token.symbol() {
+            0..=255 => Code::Literal(token.symbol() as u8),
+            256 => Code::EndOfBlock,
+            _ => Code::Pointer {
+                distance: token.distance(),
+                length: token.length(),
+            },
+        }
+    }
+}
 impl Code {
     pub fn literal_code(&self) -> u16 {
         match *self {
             Code::Literal(x) => x as u16,
             Code::EndOfBlock => END_OF_BLOCK,
-            Code::Pointer { length: l, .. } => match l {
-                0x00..=0x07 => 257 + l as u16,
-                0x08..=0x0F => 265 + (l as u16 - 0x08) / 0x02,
-                0x10..=0x1F => 269 + (l as u16 - 0x10) / 0x04,
-                0x20..=0x3F => 273 + (l as u16 - 0x20) / 0x08,
-                0x40..=0x7F => 277 + (l as u16 - 0x40) / 0x10,
-                0x80..=0xFE => 281 + (l as u16 - 0x80) / 0x20,
-                0xFF => 285,
-            },
+            Code::Pointer { length, .. } => 257 + LENGTH_SYMBOLS[length as usize] as u16,
         }
     }
     pub fn extra_length(&self) -> Option<(u8, u16)> {
-        if let Code::Pointer { length: l, .. } = *self {
-            match l {
-                0x00..=0x07 | 0xFF => None,
-                0x08..=0x0F => Some((1, (l as u16 - 0x08) % 0x02)),
-                0x10..=0x1F => Some((2, (l as u16 - 0x10) % 0x04)),
-                0x20..=0x3F => Some((3, (l as u16 - 0x20) % 0x08)),
-                0x40..=0x7F => Some((4, (l as u16 - 0x40) % 0x10)),
-                0x80..=0xFE => Some((5, (l as u16 - 0x80) % 0x20)),
-            }
-        } else {
-            None
+        let length = match *self {
+            Code::Pointer { length, .. } => length,
+            _ => return None,
+        };
+        let (base, width) = LENGTH_TABLE[LENGTH_SYMBOLS[length as usize] as usize];
+        if width == 0 {
+            return None;
         }
+        Some((width, (length - base) as u16))
     }
     pub fn distance_code(&self) -> Option<(u16, u8, u16)> {
-        if let Code::Pointer { distance: d, .. } = *self {
-            let d = d - 1;
-            match d {
-                0x0000..=0x0003 => Some((d, 0, 0)),
-                0x0004..=0x0007 => {
-                    let (div, rem) = div_rem(d - 0x0004, 0x0002);
-                    Some((4 + div, 1, rem))
-                }
-                0x0008..=0x000F => {
-                    let (div, rem) = div_rem(d - 0x0008, 0x0004);
-                    Some((6 + div, 2, rem))
-                }
-                0x0010..=0x001F => {
-                    let (div, rem) = div_rem(d - 0x0010, 0x0008);
-                    Some((8 + div, 3, rem))
-                }
-                0x0020..=0x003F => {
-                    let (div, rem) = div_rem(d - 0x0020, 0x0010);
-                    Some((10 + div, 4, rem))
-                }
-                0x0040..=0x007F => {
-                    let (div, rem) = div_rem(d - 0x0040, 0x0020);
-                    Some((12 + div, 5, rem))
-                }
-                0x0080..=0x00FF => {
-                    let (div, rem) = div_rem(d - 0x0080, 0x0040);
-                    Some((14 + div, 6, rem))
-                }
-                0x0100..=0x01FF => {
-                    let (div, rem) = div_rem(d - 0x0100, 0x0080);
-                    Some((16 + div, 7, rem))
-                }
-                0x0200..=0x03FF => {
-                    let (div, rem) = div_rem(d - 0x0200, 0x0100);
-                    Some((18 + div, 8, rem))
-                }
-                0x0400..=0x07FF => {
-                    let (div, rem) = div_rem(d - 0x0400, 0x0200);
-                    Some((20 + div, 9, rem))
-                }
-                0x0800..=0x0FFF => {
-                    let (div, rem) = div_rem(d - 0x0800, 0x0400);
-                    Some((22 + div, 10, rem))
-                }
-                0x1000..=0x1FFF => {
-                    let (div, rem) = div_rem(d - 0x1000, 0x0800);
-                    Some((24 + div, 11, rem))
-                }
-                0x2000..=0x3FFF => {
-                    let (div, rem) = div_rem(d - 0x2000, 0x1000);
-                    Some((26 + div, 12, rem))
-                }
-                0x4000..=0x7FFF => {
-                    let (div, rem) = div_rem(d - 0x4000, 0x2000);
-                    Some((28 + div, 13, rem))
-                }
-                _ => unimplemented!(),
-            }
-        } else {
-            None
-        }
+        // Distances out of 1..=32768 have no code.
+        let token = match *self {
+            Code::Pointer {
+                distance: 1..=MAX_DISTANCE,
+                ..
+            } => Token::from(*self),
+            _ => return None,
+        };
+        let bits = token.distance_extra();
+        Some((token.distance_symbol() as u16, bits.width, bits.data))
     }
 }
 // Functions.
-fn div_rem(a: u16, b: u16) -> (u16, u16) {
-    (a / b, a % b)
+const fn length_symbols() -> [u8; 256] {
+    let mut table = [0; 256];
+    let mut symbol = 0;
+    while symbol < LENGTH_TABLE.len() {
+        let (base, width) = LENGTH_TABLE[symbol];
+        let mut length = base as usize;
+        while length < base as usize + (1 << width) && length < 256 {
+            table[length] = symbol as u8;
+            length += 1;
+        }
+        symbol += 1;
+    }
+    table
+}
+const fn distance_symbols() -> [u8; 512] {
+    // As zlib: distances above 256 are looked up by their high bits.
+    let mut table = [0; 512];
+    let mut symbol = 0;
+    while symbol < DISTANCE_TABLE.len() {
+        let (base, width) = DISTANCE_TABLE[symbol];
+        let mut d = base as usize - 1;
+        while d < base as usize - 1 + (1 << width) {
+            if d < 256 {
+                table[d] = symbol as u8;
+            } else {
+                table[256 + (d >> 7)] = symbol as u8;
+            }
+            d += 1;
+        }
+        symbol += 1;
+    }
+    table
 }
@@ -0,0 +1,806 @@
+//! # Zlib-identical output
+//!
+//! Deflate and zlib streams which are byte for byte the ones of reference
+//! zlib (1.2.13) at a given level, with its default window and memory:
+//! - Levels 1 to 3: deflate_fast, no lazy matching.
+//! - Levels 4 to 9: deflate_slow, lazy matching.
+//! - Same hash chains, block flushes after 16383 symbols, same trees and
+//!   the same choice between stored, fixed and dynamic blocks.
+//!
+//! Gzip members are the ones of zlib's own gzip wrapper (`windowBits` 31): no
+//! name, no time and the Unix OS code. GNU gzip has a deflate of its own, whose
+//! output differs from zlib's below level 9.
+//!
+//! Checksums of the outputs then match files made by other toolchains.
+//!
+//! ## Examples
+//!
+//! ### Golden vectors.
+//! ```
+//! use devker::prelude::{deflate_compat, zlib_encode_compat, Cache};
+//!
+//! let mut cache = Cache::new();
+//! let v_in = b"Hello world, this is a wonderful world ! Hello world, hello !";
+//!
+//! // zlib.compress(v_in, 6)
+//! let encoded = zlib_encode_compat(v_in, 6, &mut cache).unwrap();
+//! let golden = [
+//!     0x78, 0x9c, 0xf3, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0x28, 0xcf, 0x2f, 0xca, 0x49, 0xd1, 0x51,
+//!     0x28, 0xc9, 0xc8, 0x2c, 0x56, 0x00, 0xa2, 0x44, 0xa0, 0x40, 0x5e, 0x4a, 0x6a, 0x51, 0x5a,
+//!     0x69, 0x0e, 0x44, 0x4a, 0x41, 0x51, 0xc1, 0x03, 0x59, 0x61, 0x06, 0x98, 0xa3, 0x08, 0x00,
+//!     0xa1, 0x30, 0x15, 0x3a,
+//! ];
+//! assert_eq!(encoded, golden);
+//!
+//! // zlib.compressobj(1, zlib.DEFLATED, -15)
+//! let encoded = deflate_compat(&v_in.repeat(3), 1, &mut cache).unwrap();
+//! let golden = [
+//!     0xf3, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0x28, 0xcf, 0x2f, 0xca, 0x49, 0xd1, 0x51, 0x28, 0xc9,
+//!     0xc8, 0x2c, 0x56, 0x00, 0xa2, 0x44, 0xa0, 0x40, 0x5e, 0x4a, 0x6a, 0x51, 0x5a, 0x69, 0x0e,
+//!     0x44, 0x4a, 0x41, 0x51, 0xc1, 0x03, 0x59, 0x61, 0x06, 0x98, 0xa3, 0x88, 0x22, 0x46, 0x57,
+//!     0xcd, 0x00,
+//! ];
+//! assert_eq!(encoded, golden);
+//! ```
+//!
+//! ### Reference files.
+//! ```
+//! use devker::prelude::{deflate_compat, gzip_encode_compat, zlib_encode_compat, Cache};
+//!
+//! // Inputs and files of `data/compat/gen.py`, made by zlib 1.2.13.
+//! let read = |name: String| {
+//!     std::fs::read(format!("{}/data/compat/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+//! };
+//! let words: Vec<&str> = "lorem ipsum dolor sit amet, consectetur adipiscing elit sed do \
+//!     eiusmod tempor incididunt ut labore et dolore magna aliqua."
+//!     .split_whitespace()
+//!     .collect();
+//! let lcg = |x: u32| x.wrapping_mul(1103515245).wrapping_add(12345);
+//! let (mut x, mut text) = (1, Vec::new());
+//! while text.len() < 160_000 {
+//!     x = lcg(x);
+//!     text.extend(format!("{} {} ", words[(x >> 16) as usize % words.len()], x >> 24).bytes());
+//! }
+//! text.truncate(160_000);
+//! let (mut x, mut noise) = (1, Vec::new());
+//! for _ in 0..20_000 {
+//!     x = lcg(x);
+//!     noise.push((x >> 24) as u8);
+//! }
+//!
+//! let mut cache = Cache::new();
+//! // Over 64 KB and two blocks at each level.
+//! for level in 1..=9 {
+//!     let golden = read(format!("text-{}.zz", level));
+//!     assert_eq!(zlib_encode_compat(&text, level, &mut cache).unwrap(), golden);
+//!     let encoded = deflate_compat(&text, level, &mut cache).unwrap();
+//!     assert_eq!(encoded, golden[2..golden.len() - 4]);
+//! }
+//! // Stored blocks, which are shorter than any code.
+//! let encoded = zlib_encode_compat(&noise, 6, &mut cache).unwrap();
+//! assert_eq!(encoded, read("noise-6.zz".into()));
+//! // Gzip, with the extra flags of the fastest and best levels.
+//! for level in [1, 6, 9].iter() {
+//!     let encoded = gzip_encode_compat(&text[..4096], *level, &mut cache).unwrap();
+//!     assert_eq!(encoded, read(format!("short-{}.gz", level)));
+//! }
+//! ```
+
+// Imports.
+use crate::adler32::Adler32;
+use crate::bits::{BitWriter, Bits};
+use crate::code::{Token, WIDTH_CODE_ORDER};
+use crate::crc32::Crc32;
+use crate::gzip::{ID, METHOD_DEFLATE};
+use crate::huffman::{fixed_widthes, Encoder};
+use crate::prelude::{BlockType, Cache};
+// Constants.
+const ERROR_LEVEL: &str = "Compression level must be between 1 and 9.";
+const WSIZE: usize = 0x8000;
+const WMASK: usize = WSIZE - 1;
+const WINDOW_SIZE: usize = 2 * WSIZE;
+const HASH_SIZE: usize = 0x8000;
+const HASH_MASK: usize = HASH_SIZE - 1;
+const HASH_SHIFT: usize = 5;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MIN_LOOKAHEAD: usize = MAX_MATCH + MIN_MATCH + 1;
+const MAX_DIST: usize = WSIZE - MIN_LOOKAHEAD;
+const WIN_INIT: usize = MAX_MATCH;
+const OS_UNIX: u8 = 3;
+const TOO_FAR: usize = 4096;
+const LIT_BUFSIZE: usize = 0x4000;
+const L_CODES: usize = 286;
+const D_CODES: usize = 30;
+const BL_CODES: usize = 19;
+const HEAP_SIZE: usize = 2 * L_CODES + 1;
+const MAX_BITS: u8 = 15;
+const MAX_BL_BITS: u8 = 7;
+const EXTRA_LBITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const EXTRA_DBITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const EXTRA_BLBITS: [u8; 19] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 3, 7];
+// (good_length, max_lazy, nice_length, max_chain) for each level.
+const CONFIGURATION_TABLE: [(usize, usize, usize, usize); 10] = [
+    (0, 0, 0, 0),
+    (4, 4, 8, 4),
+    (4, 5, 16, 8),
+    (4, 6, 32, 32),
+    (4, 4, 16, 16),
+    (8, 16, 32, 32),
+    (8, 16, 128, 128),
+    (8, 32, 128, 256),
+    (32, 128, 258, 1024),
+    (32, 258, 258, 4096),
+];
+// Structures.
+struct Tree<'a> {
+    frequencies: &'a [usize],
+    static_widthes: Option<&'a [u8]>,
+    extra: &'a [u8],
+    base: usize,
+    max_width: u8,
+}
+struct Deflater<'a> {
+    v_in: &'a [u8],
+    pos: usize,
+    window: Vec<u8>,
+    head: &'a mut [i32],
+    prev: &'a mut [i32],
+    ins_h: usize,
+    strstart: usize,
+    block_start: isize,
+    lookahead: usize,
+    insert: usize,
+    high_water: usize,
+    match_length: usize,
+    match_start: usize,
+    prev_length: usize,
+    prev_match: usize,
+    match_available: bool,
+    good_match: usize,
+    max_lazy: usize,
+    nice_match: usize,
+    max_chain: usize,
+    tokens: Vec<Token>,
+    writer: BitWriter,
+}
+// Implementations.
+impl<'a> Tree<'a> {
+    fn smaller(frequencies: &[usize], depth: &[u8], n: usize, m: usize) -> bool {
+        frequencies[n] < frequencies[m]
+            || (frequencies[n] == frequencies[m] && depth[n] <= depth[m])
+    }
+    fn pqdownheap(
+        heap: &mut [usize],
+        heap_len: usize,
+        frequencies: &[usize],
+        depth: &[u8],
+        mut k: usize,
+    ) {
+        let v = heap[k];
+        let mut j = k << 1;
+        while j <= heap_len {
+            if j < heap_len && Self::smaller(frequencies, depth, heap[j + 1], heap[j]) {
+                j += 1;
+            }
+            if Self::smaller(frequencies, depth, v, heap[j]) {
+                break;
+            }
+            heap[k] = heap[j];
+            k = j;
+            j <<= 1;
+        }
+        heap[k] = v;
+    }
+    // Returns the code widthes and max_code, as build_tree() in trees.c.
+    fn build(&self, opt_len: &mut isize, static_len: &mut isize) -> (Vec<u8>, usize) {
+        let elems = self.frequencies.len();
+        let mut frequencies = vec![0; 2 * elems + 1];
+        let mut dad = vec![0; 2 * elems + 1];
+        let mut widthes = vec![0u16; 2 * elems + 1];
+        let mut depth = vec![0u8; 2 * elems + 1];
+        let mut heap = [0; HEAP_SIZE + 1];
+        let (mut heap_len, mut heap_max) = (0, HEAP_SIZE);
+        let mut max_code: isize = -1;
+
+        frequencies[..elems].copy_from_slice(self.frequencies);
+        for n in (0..elems).filter(|n| frequencies[*n] != 0) {
+            heap_len += 1;
+            heap[heap_len] = n;
+            max_code = n as isize;
+        }
+        while heap_len < 2 {
+            let node = if max_code < 2 {
+                max_code += 1;
+                max_code as usize
+            } else {
+                0
+            };
+            heap_len += 1;
+            heap[heap_len] = node;
+            frequencies[node] = 1;
+            depth[node] = 0;
+            *opt_len -= 1;
+            if let Some(static_widthes) = self.static_widthes {
+                *static_len -= static_widthes[node] as isize;
+            }
+        }
+        let max_code = max_code as usize;
+        for n in (1..=heap_len / 2).rev() {
+            Self::pqdownheap(&mut heap, heap_len, &frequencies, &depth, n);
+        }
+        let mut node = elems;
+        loop {
+            let n = heap[1];
+            heap[1] = heap[heap_len];
+            heap_len -= 1;
+            Self::pqdownheap(&mut heap, heap_len, &frequencies, &depth, 1);
+            let m = heap[1];
+            heap_max -= 1;
+            heap[heap_max] = n;
+            heap_max -= 1;
+            heap[heap_max] = m;
+            frequencies[node] = frequencies[n] + frequencies[m];
+            depth[node] = std::cmp::max(depth[n], depth[m]) + 1;
+            dad[n] = node;
+            dad[m] = node;
+            heap[1] = node;
+            node += 1;
+            Self::pqdownheap(&mut heap, heap_len, &frequencies, &depth, 1);
+            if heap_len < 2 {
+                break;
+            }
+        }
+        heap_max -= 1;
+        heap[heap_max] = heap[1];
+
+        // gen_bitlen()
+        let mut bl_count = [0usize; MAX_BITS as usize + 1];
+        let mut overflow = 0;
+        widthes[heap[heap_max]] = 0;
+        for &n in &heap[heap_max + 1..HEAP_SIZE] {
+            let mut bits = widthes[dad[n]] + 1;
+            if bits > self.max_width as u16 {
+                bits = self.max_width as u16;
+                overflow += 1;
+            }
+            widthes[n] = bits;
+            if n > max_code {
+                continue;
+            }
+            bl_count[bits as usize] += 1;
+            let xbits = if n >= self.base {
+                self.extra[n - self.base] as isize
+            } else {
+                0
+            };
+            let f = frequencies[n] as isize;
+            *opt_len += f * (bits as isize + xbits);
+            if let Some(static_widthes) = self.static_widthes {
+                *static_len += f * (static_widthes[n] as isize + xbits);
+            }
+        }
+        if overflow > 0 {
+            let max_width = self.max_width as usize;
+            while overflow > 0 {
+                let mut bits = max_width - 1;
+                while bl_count[bits] == 0 {
+                    bits -= 1;
+                }
+                bl_count[bits] -= 1;
+                bl_count[bits + 1] += 2;
+                bl_count[max_width] -= 1;
+                overflow -= 2;
+            }
+            let mut h = HEAP_SIZE;
+            for bits in (1..=max_width).rev() {
+                let mut n = bl_count[bits];
+                while n != 0 {
+                    h -= 1;
+                    let m = heap[h];
+                    if m > max_code {
+                        continue;
+                    }
+                    if widthes[m] as usize != bits {
+                        *opt_len += (bits as isize - widthes[m] as isize) * frequencies[m] as isize;
+                        widthes[m] = bits as u16;
+                    }
+                    n -= 1;
+                }
+            }
+        }
+        let widthes = (0..elems)
+            .map(|n| if n <= max_code { widthes[n] as u8 } else { 0 })
+            .collect();
+        (widthes, max_code)
+    }
+}
+impl<'a> Deflater<'a> {
+    fn new(v_in: &'a [u8], level: usize, buf: &'a mut [i32; 0x10000]) -> Self {
+        let (head, prev) = buf.split_at_mut(HASH_SIZE);
+        head.fill(0);
+        prev.fill(0);
+        let (good_match, max_lazy, nice_match, max_chain) = CONFIGURATION_TABLE[level];
+        Self {
+            v_in,
+            pos: 0,
+            window: vec![0; WINDOW_SIZE],
+            head,
+            prev,
+            ins_h: 0,
+            strstart: 0,
+            block_start: 0,
+            lookahead: 0,
+            insert: 0,
+            high_water: 0,
+            match_length: MIN_MATCH - 1,
+            match_start: 0,
+            prev_length: MIN_MATCH - 1,
+            prev_match: 0,
+            match_available: false,
+            good_match,
+            max_lazy,
+            nice_match,
+            max_chain,
+            tokens: Vec::with_capacity(LIT_BUFSIZE),
+            writer: BitWriter::new(),
+        }
+    }
+    fn update_hash(&mut self, c: u8) {
+        self.ins_h = ((self.ins_h << HASH_SHIFT) ^ c as usize) & HASH_MASK;
+    }
+    fn insert_string(&mut self, s: usize) -> usize {
+        self.update_hash(self.window[s + MIN_MATCH - 1]);
+        let hash_head = self.head[self.ins_h] as usize;
+        self.prev[s & WMASK] = hash_head as i32;
+        self.head[self.ins_h] = s as i32;
+        hash_head
+    }
+    fn slide_hash(&mut self) {
+        for x in self.head.iter_mut().chain(self.prev.iter_mut()) {
+            *x = if *x as usize >= WSIZE {
+                *x - WSIZE as i32
+            } else {
+                0
+            };
+        }
+    }
+    fn fill_window(&mut self) {
+        loop {
+            let mut more = WINDOW_SIZE - self.lookahead - self.strstart;
+            if self.strstart >= WSIZE + MAX_DIST {
+                self.window.copy_within(WSIZE..2 * WSIZE - more, 0);
+                self.match_start = self.match_start.wrapping_sub(WSIZE);
+                self.strstart -= WSIZE;
+                self.block_start -= WSIZE as isize;
+                if self.insert > self.strstart {
+                    self.insert = self.strstart;
+                }
+                self.slide_hash();
+                more += WSIZE;
+            }
+            if self.pos == self.v_in.len() {
+                break;
+            }
+            let n = std::cmp::min(more, self.v_in.len() - self.pos);
+            let start = self.strstart + self.lookahead;
+            self.window[start..start + n].copy_from_slice(&self.v_in[self.pos..self.pos + n]);
+            self.pos += n;
+            self.lookahead += n;
+
+            if self.lookahead + self.insert >= MIN_MATCH {
+                let mut s = self.strstart - self.insert;
+                self.ins_h = self.window[s] as usize;
+                self.update_hash(self.window[s + 1]);
+                while self.insert > 0 {
+                    self.insert_string(s);
+                    s += 1;
+                    self.insert -= 1;
+                    if self.lookahead + self.insert < MIN_MATCH {
+                        break;
+                    }
+                }
+            }
+            if self.lookahead >= MIN_LOOKAHEAD || self.pos == self.v_in.len() {
+                break;
+            }
+        }
+
+        // Zero the bytes after the input, which are read by longest_match().
+        if self.high_water < WINDOW_SIZE {
+            let curr = self.strstart + self.lookahead;
+            if self.high_water < curr {
+                let init = std::cmp::min(WINDOW_SIZE - curr, WIN_INIT);
+                self.window[curr..curr + init].fill(0);
+                self.high_water = curr + init;
+            } else if self.high_water < curr + WIN_INIT {
+                let init = curr + WIN_INIT - self.high_water;
+                let init = std::cmp::min(init, WINDOW_SIZE - self.high_water);
+                self.window[self.high_water..self.high_water + init].fill(0);
+                self.high_water += init;
+            }
+        }
+    }
+    fn longest_match(&mut self, mut cur_match: usize) -> usize {
+        let mut chain_length = self.max_chain;
+        let scan = self.strstart;
+        let mut best_len = self.prev_length;
+        let mut nice_match = self.nice_match;
+        let limit = self.strstart.saturating_sub(MAX_DIST);
+        let window = &self.window;
+        let mut scan_end1 = window[scan + best_len - 1];
+        let mut scan_end = window[scan + best_len];
+
+        if self.prev_length >= self.good_match {
+            chain_length >>= 2;
+        }
+        if nice_match > self.lookahead {
+            nice_match = self.lookahead;
+        }
+        loop {
+            let m = cur_match;
+            if window[m + best_len] == scan_end
+                && window[m + best_len - 1] == scan_end1
+                && window[m] == window[scan]
+                && window[m + 1] == window[scan + 1]
+            {
+                // The third bytes are equal, given that the hash keys are equal.
+                let len = (3..MAX_MATCH)
+                    .find(|i| window[scan + i] != window[m + i])
+                    .unwrap_or(MAX_MATCH);
+                if len > best_len {
+                    self.match_start = cur_match;
+                    best_len = len;
+                    if len >= nice_match {
+                        break;
+                    }
+                    scan_end1 = window[scan + best_len - 1];
+                    scan_end = window[scan + best_len];
+                }
+            }
+            cur_match = self.prev[cur_match & WMASK] as usize;
+            chain_length -= 1;
+            if cur_match <= limit || chain_length == 0 {
+                break;
+            }
+        }
+        std::cmp::min(best_len, self.lookahead)
+    }
+    fn tally(&mut self, token: Token) -> bool {
+        self.tokens.push(token);
+        self.tokens.len() == LIT_BUFSIZE - 1
+    }
+    fn tally_literal(&mut self, pos: usize) -> bool {
+        self.tally(Token::literal(self.window[pos]))
+    }
+    fn tally_pointer(&mut self, distance: usize, length: usize) -> bool {
+        self.tally(Token::pointer(distance as u16, (length - MIN_MATCH) as u8))
+    }
+    fn flush_block(&mut self, last: bool) {
+        let stored_len = (self.strstart as isize - self.block_start) as usize;
+        let stored = if self.block_start >= 0 {
+            let start = self.block_start as usize;
+            Some(&self.window[start..start + stored_len])
+        } else {
+            None
+        };
+        flush_tokens(&mut self.writer, &self.tokens, stored, last);
+        self.tokens.clear();
+        self.block_start = self.strstart as isize;
+    }
+    fn deflate_fast(&mut self) {
+        loop {
+            if self.lookahead < MIN_LOOKAHEAD {
+                self.fill_window();
+                if self.lookahead == 0 {
+                    break;
+                }
+            }
+            let mut hash_head = 0;
+            if self.lookahead >= MIN_MATCH {
+                hash_head = self.insert_string(self.strstart);
+            }
+            if hash_head != 0 && self.strstart - hash_head <= MAX_DIST {
+                self.match_length = self.longest_match(hash_head);
+            }
+            let bflush = if self.match_length >= MIN_MATCH {
+                let distance = self.strstart - self.match_start;
+                let bflush = self.tally_pointer(distance, self.match_length);
+                self.lookahead -= self.match_length;
+                // The max_lazy parameter is max_insert_length with deflate_fast.
+                if self.match_length <= self.max_lazy && self.lookahead >= MIN_MATCH {
+                    self.match_length -= 1;
+                    while self.match_length != 0 {
+                        self.strstart += 1;
+                        self.insert_string(self.strstart);
+                        self.match_length -= 1;
+                    }
+                    self.strstart += 1;
+                } else {
+                    self.strstart += self.match_length;
+                    self.match_length = 0;
+                    self.ins_h = self.window[self.strstart] as usize;
+                    self.update_hash(self.window[self.strstart + 1]);
+                }
+                bflush
+            } else {
+                let bflush = self.tally_literal(self.strstart);
+                self.lookahead -= 1;
+                self.strstart += 1;
+                bflush
+            };
+            if bflush {
+                self.flush_block(false);
+            }
+        }
+        self.flush_block(true);
+    }
+    fn deflate_slow(&mut self) {
+        loop {
+            if self.lookahead < MIN_LOOKAHEAD {
+                self.fill_window();
+                if self.lookahead == 0 {
+                    break;
+                }
+            }
+            let mut hash_head = 0;
+            if self.lookahead >= MIN_MATCH {
+                hash_head = self.insert_string(self.strstart);
+            }
+            self.prev_length = self.match_length;
+            self.prev_match = self.match_start;
+            self.match_length = MIN_MATCH - 1;
+
+            if hash_head != 0
+                && self.prev_length < self.max_lazy
+                && self.strstart - hash_head <= MAX_DIST
+            {
+                self.match_length = self.longest_match(hash_head);
+                if self.match_length == MIN_MATCH && self.strstart - self.match_start > TOO_FAR {
+                    self.match_length = MIN_MATCH - 1;
+                }
+            }
+            if self.prev_length >= MIN_MATCH && self.match_length <= self.prev_length {
+                let max_insert = self.strstart + self.lookahead - MIN_MATCH;
+                let distance = self.strstart - 1 - self.prev_match;
+                let bflush = self.tally_pointer(distance, self.prev_length);
+                self.lookahead -= self.prev_length - 1;
+                self.prev_length -= 2;
+                while self.prev_length != 0 {
+                    self.strstart += 1;
+                    if self.strstart <= max_insert {
+                        self.insert_string(self.strstart);
+                    }
+                    self.prev_length -= 1;
+                }
+                self.match_available = false;
+                self.match_length = MIN_MATCH - 1;
+                self.strstart += 1;
+                if bflush {
+                    self.flush_block(false);
+                }
+            } else if self.match_available {
+                if self.tally_literal(self.strstart - 1) {
+                    self.flush_block(false);
+                }
+                self.strstart += 1;
+                self.lookahead -= 1;
+            } else {
+                self.match_available = true;
+                self.strstart += 1;
+                self.lookahead -= 1;
+            }
+        }
+        if self.match_available {
+            self.tokens
+                .push(Token::literal(self.window[self.strstart - 1]));
+            self.match_available = false;
+        }
+        self.flush_block(true);
+    }
+}
+// Functions.
+fn scan_tree(frequencies: &mut [usize; BL_CODES], widthes: &[u8]) -> Vec<(u8, Bits)> {
+    // Run-length encoding, as scan_tree() and send_tree() in trees.c.
+    let mut codes = Vec::new();
+    let mut prevlen = -1;
+    let mut count = 0;
+    let next = |n: usize| widthes.get(n).map_or(-2, |x| *x as i32);
+    let mut nextlen = next(0);
+    let (mut max_count, mut min_count) = if nextlen == 0 { (138, 3) } else { (7, 4) };
+    for n in 0..widthes.len() {
+        let curlen = nextlen;
+        nextlen = next(n + 1);
+        count += 1;
+        if count < max_count && curlen == nextlen {
+            continue;
+        } else if count < min_count {
+            codes.extend(std::iter::repeat_n((curlen as u8, Bits::new(0, 0)), count));
+        } else if curlen != 0 {
+            if curlen != prevlen {
+                codes.push((curlen as u8, Bits::new(0, 0)));
+                count -= 1;
+            }
+            codes.push((16, Bits::new(count as u16 - 3, 2)));
+        } else if count <= 10 {
+            codes.push((17, Bits::new(count as u16 - 3, 3)));
+        } else {
+            codes.push((18, Bits::new(count as u16 - 11, 7)));
+        }
+        count = 0;
+        prevlen = curlen;
+        (max_count, min_count) = if nextlen == 0 {
+            (138, 3)
+        } else if curlen == nextlen {
+            (6, 3)
+        } else {
+            (7, 4)
+        };
+    }
+    for (code, _) in &codes {
+        frequencies[*code as usize] += 1;
+    }
+    codes
+}
+fn flush_tokens(writer: &mut BitWriter, tokens: &[Token], stored: Option<&[u8]>, last: bool) {
+    // Frequencies.
+    let mut lfrequencies = [0; L_CODES];
+    let mut dfrequencies = [0; D_CODES];
+    for token in tokens.iter().chain(std::iter::once(&Token::END_OF_BLOCK)) {
+        lfrequencies[token.symbol()] += 1;
+        if token.is_pointer() {
+            dfrequencies[token.distance_symbol()] += 1;
+        }
+    }
+
+    // Trees.
+    let (static_lwidthes, static_dwidthes) = fixed_widthes();
+    let (mut opt_len, mut static_len) = (0, 0);
+    let ltree = Tree {
+        frequencies: &lfrequencies,
+        static_widthes: Some(&static_lwidthes),
+        extra: &EXTRA_LBITS,
+        base: 257,
+        max_width: MAX_BITS,
+    };
+    let (lwidthes, lmax_code) = ltree.build(&mut opt_len, &mut static_len);
+    let dtree = Tree {
+        frequencies: &dfrequencies,
+        static_widthes: Some(&static_dwidthes),
+        extra: &EXTRA_DBITS,
+        base: 0,
+        max_width: MAX_BITS,
+    };
+    let (dwidthes, dmax_code) = dtree.build(&mut opt_len, &mut static_len);
+    let mut wfrequencies = [0; BL_CODES];
+    let lcodes = scan_tree(&mut wfrequencies, &lwidthes[..=lmax_code]);
+    let dcodes = scan_tree(&mut wfrequencies, &dwidthes[..=dmax_code]);
+    let wtree = Tree {
+        frequencies: &wfrequencies,
+        static_widthes: None,
+        extra: &EXTRA_BLBITS,
+        base: 0,
+        max_width: MAX_BL_BITS,
+    };
+    let (wwidthes, _) = wtree.build(&mut opt_len, &mut static_len);
+    let wcount = (3..BL_CODES)
+        .rev()
+        .find(|i| wwidthes[WIDTH_CODE_ORDER[*i]] != 0)
+        .unwrap_or(2)
+        + 1;
+    opt_len += 3 * wcount as isize + 5 + 5 + 4;
+
+    // Block type.
+    let mut opt_lenb = (opt_len as usize + 3 + 7) >> 3;
+    let static_lenb = (static_len as usize + 3 + 7) >> 3;
+    if static_lenb <= opt_lenb {
+        opt_lenb = static_lenb;
+    }
+    let (lwidthes, dwidthes) = match stored {
+        Some(stored) if stored.len() + 4 <= opt_lenb => {
+            let len = stored.len() as u16;
+            writer.write(last as u32 | (BlockType::Raw as u32) << 1, 3);
+            writer.write_bytes(&len.to_le_bytes());
+            writer.write_bytes(&(!len).to_le_bytes());
+            writer.write_bytes(stored);
+            return;
+        }
+        _ if static_lenb == opt_lenb => {
+            writer.write(last as u32 | (BlockType::Fixed as u32) << 1, 3);
+            (static_lwidthes.to_vec(), static_dwidthes.to_vec())
+        }
+        _ => {
+            writer.write(last as u32 | (BlockType::Dynamic as u32) << 1, 3);
+            writer.write((lmax_code + 1 - 257) as u32, 5);
+            writer.write(dmax_code as u32, 5);
+            writer.write((wcount - 4) as u32, 4);
+            for i in WIDTH_CODE_ORDER.iter().take(wcount) {
+                writer.write(wwidthes[*i] as u32, 3);
+            }
+            let encoder = Encoder::unchecked(&wwidthes);
+            for (code, extra) in lcodes.iter().chain(&dcodes) {
+                encoder.encode(writer, *code as usize);
+                writer.write_bits(*extra);
+            }
+            (lwidthes, dwidthes)
+        }
+    };
+
+    // Tokens.
+    let literal = Encoder::unchecked(&lwidthes);
+    let distance = Encoder::unchecked(&dwidthes);
+    for token in tokens.iter().chain(std::iter::once(&Token::END_OF_BLOCK)) {
+        literal.encode(writer, token.symbol());
+        if token.is_pointer() {
+            writer.write_bits(token.length_extra());
+            distance.encode(writer, token.distance_symbol());
+            writer.write_bits(token.distance_extra());
+        }
+    }
+}
+// Main functions.
+pub fn deflate_compat(v_in: &[u8], level: u8, cache: &mut Cache) -> Result<Vec<u8>, String> {
+    // Conditions.
+    if !(1..=9).contains(&level) {
+        return Err(ERROR_LEVEL.into());
+    }
+    // Variable initialization.
+    let mut deflater = Deflater::new(v_in, level as usize, cache.inner_mut());
+
+    // Algorithm.
+    if level <= 3 {
+        deflater.deflate_fast();
+    } else {
+        deflater.deflate_slow();
+    }
+    Ok(deflater.writer.finish())
+}
+
+pub fn zlib_encode_compat(v_in: &[u8], level: u8, cache: &mut Cache) -> Result<Vec<u8>, String> {
+    // Variable initialization.
+    let cmf = 0x78;
+    let flevel = match level {
+        0..=1 => 0,
+        2..=5 => 1,
+        6 => 2,
+        _ => 3,
+    };
+    let flg: u16 = flevel << 6;
+    let flg = flg + 31 - ((cmf as u16) << 8 | flg) % 31;
+    let mut adler32 = Adler32::new();
+
+    // Algorithm.
+    let mut data = deflate_compat(v_in, level, cache)?;
+    adler32.update(v_in);
+    let mut v_out = Vec::with_capacity(data.len() + 2 + 4);
+    v_out.extend(&[cmf, flg as u8]);
+    v_out.append(&mut data);
+    v_out.extend(&adler32.checksum());
+    Ok(v_out)
+}
+
+pub fn gzip_encode_compat(v_in: &[u8], level: u8, cache: &mut Cache) -> Result<Vec<u8>, String> {
+    // Variable initialization.
+    let xfl = match level {
+        1 => 4,
+        9 => 2,
+        _ => 0,
+    };
+    let mut crc32 = Crc32::new();
+
+    // Algorithm.
+    let mut data = deflate_compat(v_in, level, cache)?;
+    crc32.update(v_in);
+    let mut v_out = Vec::with_capacity(data.len() + 10 + 8);
+    v_out.extend(&ID);
+    v_out.extend(&[METHOD_DEFLATE, 0, 0, 0, 0, 0, xfl, OS_UNIX]);
+    v_out.append(&mut data);
+    v_out.extend(&crc32.checksum());
+    v_out.extend(&(v_in.len() as u32).to_le_bytes());
+    Ok(v_out)
+}
@@ -0,0 +1,182 @@
+//! # Concatenate
+//!
+//! Join compressed streams into one, without decoding nor encoding them again.
+//! - Deflate: the last block of every stream but the last one loses its BFINAL
+//!   bit, then an empty stored block brings the next stream on a byte boundary.
+//! - Zlib: the header of the first stream is kept, Adler-32 checksums are
+//!   combined.
+//! - Gzip: all the members of all the files are joined into one member. The
+//!   header of the first member is kept, CRC-32 checksums and sizes are
+//!   combined.
+//!
+//! ## Examples
+//!
+//! ### Easy to use.
+//! ```
+//! use devker::prelude::{zlib_concat, zlib_decode, zlib_encode, BlockType, Cache};
+//!
+//! let mut cache = Cache::new();
+//! let first = zlib_encode(b"Hello world, ", BlockType::Fixed, &mut cache);
+//! let second = zlib_encode(b"this is a wonderful world !", BlockType::Dynamic, &mut cache);
+//!
+//! let joined = zlib_concat(&[&first, &second], &mut cache).unwrap();
+//! let decoded = zlib_decode(&joined, &mut cache).unwrap();
+//! assert_eq!(decoded, b"Hello world, this is a wonderful world !");
+//! ```
+//!
+//! ### Deflate and gzip.
+//! ```
+//! use devker::prelude::{deflate, deflate_concat, gzip_concat, gzip_decode, gzip_encode, inflate, BlockType, Cache};
+//!
+//! let mut cache = Cache::new();
+//! let first = deflate(b"Hello world, ", BlockType::Raw, &mut cache);
+//! let second = deflate(b"this is a wonderful world !", BlockType::Fixed, &mut cache);
+//! let joined = deflate_concat(&[&first, &second], &mut cache).unwrap();
+//! assert_eq!(inflate(&joined, &mut cache).unwrap(), b"Hello world, this is a wonderful world !");
+//!
+//! // Two members in the first file, one in the second.
+//! let mut first = gzip_encode(b"Hello ", BlockType::Fixed, &mut cache);
+//! first.extend(gzip_encode(b"world, ", BlockType::Dynamic, &mut cache));
+//! let second = gzip_encode(b"this is a wonderful world !", BlockType::Fixed, &mut cache);
+//! let joined = gzip_concat(&[&first, &second], &mut cache).unwrap();
+//! assert_eq!(gzip_decode(&joined, &mut cache).unwrap(), b"Hello world, this is a wonderful world !");
+//! ```
+
+// Imports.
+use crate::adler32::adler32_combine;
+use crate::bits::BitWriter;
+use crate::crc32::crc32_combine;
+use crate::disasm::Disassembly;
+use crate::prelude::{disassemble, Cache, Format, InflateOptions};
+use crate::{gzip, zlib};
+// Constants.
+const ERROR_EMPTY: &str = "No stream to concatenate.";
+const ERROR_TRAILER: &str = "Stream's trailer is missing or too long.";
+// Functions.
+fn append_stream(
+    writer: &mut BitWriter,
+    v_in: &[u8],
+    disassembly: &Disassembly,
+    last: bool,
+) -> Result<usize, String> {
+    // Conditions.
+    if let Some(error) = &disassembly.error {
+        return Err(error.clone());
+    }
+    // Variable initialization.
+    let start = disassembly.header.len() * 8;
+    let block = &disassembly.blocks[disassembly.blocks.len() - 1];
+    let end = block.offset + block.compressed_size - start;
+    let mut data = v_in[start / 8..(start + end).div_ceil(8)].to_vec();
+
+    // Algorithm.
+    if !last {
+        let bfinal = block.offset - start;
+        data[bfinal / 8] &= !(1 << (bfinal % 8));
+    }
+    writer.write_bytes(&data[..end / 8]);
+    if !end.is_multiple_of(8) {
+        writer.write(data[end / 8] as u32, (end % 8) as u8);
+    }
+    if !last {
+        writer.write(0, 3);
+        writer.write_bytes(&[0, 0, 0xFF, 0xFF]);
+    }
+    Ok(disassembly.blocks.iter().map(|x| x.uncompressed_size).sum())
+}
+// Main functions.
+pub fn deflate_concat(streams: &[&[u8]], cache: &mut Cache) -> Result<Vec<u8>, String> {
+    // Conditions.
+    if streams.is_empty() {
+        return Err(ERROR_EMPTY.into());
+    }
+    // Variable initialization.
+    let mut writer = BitWriter::new();
+
+    // Algorithm.
+    for (i, stream) in streams.iter().enumerate() {
+        let disassembly = disassemble(
+            stream,
+            Format::Deflate,
+            false,
+            InflateOptions::default(),
+            cache,
+        )?;
+        append_stream(&mut writer, stream, &disassembly, i + 1 == streams.len())?;
+    }
+    Ok(writer.finish())
+}
+
+pub fn zlib_concat(streams: &[&[u8]], cache: &mut Cache) -> Result<Vec<u8>, String> {
+    // Conditions.
+    if streams.is_empty() {
+        return Err(ERROR_EMPTY.into());
+    }
+    // Variable initialization.
+    let mut writer = BitWriter::new();
+    let mut adler32 = 1;
+
+    // Algorithm.
+    for (i, stream) in streams.iter().enumerate() {
+        let disassembly = disassemble(
+            stream,
+            Format::Zlib,
+            false,
+            InflateOptions::default(),
+            cache,
+        )?;
+        if i == 0 {
+            writer.write_bytes(&disassembly.header);
+        }
+        let len = append_stream(&mut writer, stream, &disassembly, i + 1 == streams.len())?;
+        if disassembly.trailer.len() != zlib::ADLER_LEN {
+            return Err(ERROR_TRAILER.into());
+        }
+        let trailer = &disassembly.trailer;
+        let checksum = u32::from_be_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        adler32 = adler32_combine(adler32, checksum, len);
+    }
+    writer.write_bytes(&adler32.to_be_bytes());
+    Ok(writer.finish())
+}
+
+pub fn gzip_concat(files: &[&[u8]], cache: &mut Cache) -> Result<Vec<u8>, String> {
+    // Conditions.
+    if files.is_empty() {
+        return Err(ERROR_EMPTY.into());
+    }
+    // Variable initialization.
+    let mut members = Vec::new();
+    let mut writer = BitWriter::new();
+    let mut crc32 = 0;
+    let mut size = 0u32;
+
+    // Algorithm.
+    for file in files {
+        let mut rest = *file;
+        loop {
+            let disassembly =
+                disassemble(rest, Format::Gzip, false, InflateOptions::default(), cache)?;
+            if disassembly.trailer.len() < gzip::TRAILER_LEN {
+                return Err(ERROR_TRAILER.into());
+            }
+            let len = rest.len() - disassembly.trailer.len() + gzip::TRAILER_LEN;
+            members.push((&rest[..len], disassembly));
+            rest = &rest[len..];
+            if rest.is_empty() {
+                break;
+            }
+        }
+    }
+    writer.write_bytes(&members[0].1.header);
+    for (i, (member, disassembly)) in members.iter().enumerate() {
+        let len = append_stream(&mut writer, member, disassembly, i + 1 == members.len())?;
+        let trailer = &disassembly.trailer;
+        let checksum = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        crc32 = crc32_combine(crc32, checksum, len);
+        size = size.wrapping_add(len as u32);
+    }
+    writer.write_bytes(&crc32.to_le_bytes());
+    writer.write_bytes(&size.to_le_bytes());
+    Ok(writer.finish())
+}
@@ -0,0 +1,76 @@
+//! # Crc32
+
+// Constants.
+const POLYNOMIAL: u32 = 0xEDB8_8320;
+const TABLE: [u32; 256] = make_table();
+// Structures.
+#[derive(Debug, Clone, Copy)]
+pub struct Crc32(u32);
+// Implementations.
+impl Crc32 {
+    pub fn new() -> Self {
+        Self(0xFFFF_FFFF)
+    }
+    pub fn update(&mut self, buf: &[u8]) {
+        for byte in buf {
+            self.0 = TABLE[((self.0 ^ *byte as u32) & 0xFF) as usize] ^ (self.0 >> 8);
+        }
+    }
+    pub fn checksum(&self) -> [u8; 4] {
+        (!self.0).to_le_bytes()
+    }
+}
+// Functions.
+fn multmodp(a: u32, mut b: u32) -> u32 {
+    // Multiply a and b modulo the polynomial, bits are reflected.
+    let mut m = 1 << 31;
+    let mut p = 0;
+    loop {
+        if a & m != 0 {
+            p ^= b;
+            if a & (m - 1) == 0 {
+                break;
+            }
+        }
+        m >>= 1;
+        b = if b & 1 == 1 {
+            (b >> 1) ^ POLYNOMIAL
+        } else {
+            b >> 1
+        };
+    }
+    p
+}
+pub fn crc32_combine(crc1: u32, crc2: u32, len2: usize) -> u32 {
+    // x^(8 * len2) modulo the polynomial, by squaring x^8.
+    let mut x2n = 1 << 23;
+    let mut p = 1 << 31;
+    let mut n = len2;
+    while n != 0 {
+        if n & 1 == 1 {
+            p = multmodp(x2n, p);
+        }
+        x2n = multmodp(x2n, x2n);
+        n >>= 1;
+    }
+    multmodp(p, crc1) ^ crc2
+}
+const fn make_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            crc = if crc & 1 == 1 {
+                POLYNOMIAL ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            k += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
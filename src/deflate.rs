@@ -41,319 +41,776 @@
 //! let decoded = inflate(&encoded, &mut cache).unwrap();
 //! assert_eq!(v_in, decoded);
 //! ```
+//!
+//! ### Streaming.
+//! ```
+//! use devker::prelude::{deflate_stream, inflate, BlockType, Cache};
+//!
+//! let mut cache = Cache::new();
+//! let v_in = "Hello world, this is a wonderful world !".repeat(10000).into_bytes();
+//!
+//! // Memory is bounded by the window and one block, whatever the input size.
+//! let mut encoded = Vec::new();
+//! let len = deflate_stream(&v_in[..], &mut encoded, BlockType::Dynamic, &mut cache).unwrap();
+//! assert_eq!(len, v_in.len());
+//! assert_eq!(inflate(&encoded, &mut cache).unwrap(), v_in);
+//! ```
+//!
+//! ### Caller's buffers.
+//! ```
+//! use devker::prelude::{deflate, deflate_append, deflate_bound, deflate_to, inflate};
+//! use devker::prelude::{BlockType, Cache};
+//!
+//! let mut cache = Cache::new();
+//! let v_in = "Hello world, this is a wonderful world !".repeat(10).into_bytes();
+//!
+//! // Into a slice, which is large enough whatever the input.
+//! let mut buf = vec![0; deflate_bound(v_in.len(), BlockType::Dynamic)];
+//! let len = deflate_to(&v_in, BlockType::Dynamic, &mut cache, &mut buf).unwrap();
+//! assert_eq!(inflate(&buf[..len], &mut cache).unwrap(), v_in);
+//!
+//! // Blocks are written in place, a short slice fails at the first one past its end.
+//! let v_in: Vec<u8> = (0..200_000u32).map(|x| (x.wrapping_mul(x) >> 7) as u8).collect();
+//! let encoded = deflate(&v_in, BlockType::Raw, &mut cache);
+//! let mut buf = vec![0; encoded.len()];
+//! assert_eq!(deflate_to(&v_in, BlockType::Raw, &mut cache, &mut buf), Ok(encoded.len()));
+//! assert_eq!(buf, encoded);
+//! assert!(deflate_to(&v_in, BlockType::Raw, &mut cache, &mut buf[..1000]).is_err());
+//!
+//! // Appended to a vector, which can be cleared and reused.
+//! let mut encoded = Vec::new();
+//! deflate_append(&v_in, BlockType::Fixed, &mut cache, &mut encoded);
+//! assert_eq!(inflate(&encoded, &mut cache).unwrap(), v_in);
+//! ```
+//!
+//! ### Strategies.
+//! ```
+//! use devker::prelude::{deflate, inflate, BlockType, Cache, DeflateOptions, Strategy};
+//!
+//! let mut cache = Cache::new();
+//! // Rows of a bitmap: long runs of one byte.
+//! let v_in = [[0u8; 60], [255; 60]].concat().repeat(100);
+//!
+//! for strategy in [Strategy::Greedy, Strategy::HuffmanOnly, Strategy::Rle, Strategy::Filtered] {
+//!     let options = DeflateOptions {
+//!         btype: BlockType::Dynamic,
+//!         strategy,
+//!     };
+//!     let encoded = deflate(&v_in, options, &mut cache);
+//!     assert_eq!(inflate(&encoded, &mut cache).unwrap(), v_in);
+//! }
+//! ```
+//!
+//! ### Permissive decoding.
+//! ```
+//! use devker::asm::assemble;
+//! use devker::prelude::{inflate, inflate_with, Cache, InflateOptions};
+//!
+//! let mut cache = Cache::new();
+//! // Incomplete literal/length code: 'a' and end-of-block, both 2 bits wide.
+//! let text = "last\ndynamic\nlitlen 97 2\nlitlen 256 2\ndist 0 1\ndist 1 1\nliteral 'a\nend\n";
+//! let encoded = assemble(text).unwrap();
+//!
+//! // Decoding is strict by default, as in RFC 1951 and zlib.
+//! assert_eq!(inflate(&encoded, &mut cache).unwrap_err(), "Incomplete code lengths.");
+//! let options = InflateOptions { permissive: true };
+//! assert_eq!(inflate_with(&encoded, options, &mut cache).unwrap(), b"a");
+//! ```
 
 // Imports.
-use crate::bits::Bits;
-use crate::code::Code;
-use crate::code::{DISTANCE_TABLE, END_OF_BLOCK, LENGTH_TABLE};
-use crate::huffman::huffman_encode;
-use crate::lzss::{extend, lzss_encode};
+use crate::bits::{BitReader, BitWriter};
+use crate::code::{Code, Token};
+use crate::code::{DISTANCE_TABLE, END_OF_BLOCK, LENGTH_TABLE, WIDTH_CODE_ORDER};
+use crate::huffman::{canonical_codes, huffman_encode, write_block};
+use crate::lzss::{extend, Lzss, MatchFinder};
 use crate::prelude::{BlockType, Cache};
+use crate::stats::{BlockStats, Stats};
+use std::io::{ErrorKind, Read, Write};
+use std::sync::OnceLock;
+use std::time::Instant;
+use std::{cmp, mem};
 // Constants.
 const ERROR_BUFFER: &str = "Buffer overflow.";
 const ERROR_COMPLEMENT: &str = "LEN is not the one's complement of NLEN.";
+const ERROR_COUNT: &str = "Too many length or distance symbols.";
+const ERROR_DISTANCE: &str = "Invalid distance.";
+const ERROR_DISTANCE_CODE: &str = "Invalid distance code 30 or 31.";
+const ERROR_END_OF_BLOCK: &str = "Missing end-of-block code.";
+const ERROR_INCOMPLETE: &str = "Incomplete code lengths.";
 const ERROR_LENGTH: &str = "Invalid length.";
+const ERROR_LENGTH_CODE: &str = "Invalid length code 286 or 287.";
+const ERROR_OVERSUBSCRIBED: &str = "Over-subscribed code lengths.";
 const ERROR_POSITION: &str = "One distance is greater than current index.";
 const ERROR_PREVIOUS: &str = "No previous value.";
 const ERROR_RESERVED: &str = "Reserved btype.";
 const ERROR_VALUE: &str = "Invalid value decoded.";
 const ERROR_WIDTH: &str = "Invalid width decoded";
 const ERROR_WIDTHES: &str = "Invalid code lengths.";
-const WIDTH_CODE_ORDER: [usize; 19] = [
-    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
-];
+const BLOCK_LENGTH: usize = 0x10000;
+const MAX_DISTANCE: u16 = 0x8000;
+const MAX_DCOUNT: usize = 30;
+const MAX_LCOUNT: usize = 286;
+const MAX_WIDTH: u8 = 15;
+const MAX_STORED_LENGTH: usize = 0xFFFF;
+const MAX_WWIDTH: u8 = 7;
+const PRIMARY_BITS: u8 = 10;
+const PRIMARY_DBITS: u8 = 8;
+// Decode table entries: the width to skip, the kind, then a symbol, one or two
+// literals, or the width and offset of a subtable.
+const ENTRY_WIDTH: u32 = 0xF;
+const ENTRY_KIND: u32 = 0x30;
+const ENTRY_SYMBOL: u32 = 0x10;
+const ENTRY_LITERALS: u32 = 0x20;
+const ENTRY_PAIR: u32 = 0x40;
+const ENTRY_SUBTABLE: u32 = 0x30;
+// Room for a whole match and a word of overrun, below which the fast loop stops.
+const FAST_MARGIN: usize = 258 + 8;
+const UPDATE_LENGTH: usize = 0x8000;
+// The tables of fixed blocks never change: they are built once, with the length and width of the literal table, and
+// the width of the distance table.
+static FIXED_TABLES: OnceLock<(Vec<i32>, usize, u8, u8)> = OnceLock::new();
 // Structures.
 type IterU8 = dyn Iterator<Item = u8>;
+pub(crate) type Widthes = (usize, [u8; 19], Vec<u8>, Vec<u8>);
 #[derive(Debug)]
-struct Reader<'a> {
-    v_in: &'a [u8],
-    last_read: u32,
-    offset: u8,
-    last_error: Option<&'a str>,
+pub(crate) struct DecodeTable<'a> {
+    entries: &'a [i32],
+    bits: u8,
+}
+pub(crate) enum Decoded {
+    Literals([u8; 2], usize),
+    Code(Code),
 }
 #[derive(Debug)]
-struct HuffmanDecoder<'a> {
-    literal: &'a mut [i32],
-    distance: &'a mut [i32],
-    eob_width: u8,
-    max_lwidth: u8,
-    max_dwidth: u8,
+pub(crate) struct HuffmanDecoder<'a> {
+    literal: DecodeTable<'a>,
+    distance: DecodeTable<'a>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Strategy {
+    #[default]
+    Greedy,
+    HuffmanOnly,
+    Rle,
+    Filtered,
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeflateOptions {
+    pub btype: BlockType,
+    pub strategy: Strategy,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct InflateOptions {
+    pub permissive: bool,
+}
+// Where inflate writes: a vector grows as needed, a slice stays as it is.
+pub(crate) trait Output {
+    fn buffer(&mut self, len: usize) -> &mut [u8];
+    // Called once the first `i` bytes were given to `update`, gives back where decoding goes on.
+    fn slide(&mut self, i: usize) -> usize {
+        i
+    }
+}
+// A vector which only keeps the last 32 KB, which matches may refer to, once they were given to `update`.
+#[derive(Default)]
+struct Window(Vec<u8>);
+// Receives the blocks of a stream from `read_block`, the body of a Huffman block is left to `huffman`.
+pub(crate) trait Sink {
+    // BFINAL and BTYPE, which is `None` when reserved.
+    fn block(&mut self, _bfinal: bool, _btype: Option<BlockType>) {}
+    fn widthes(&mut self, _widthes: &Widthes) {}
+    // Position of the end of the header, before the data of a stored block or the codes of a Huffman block.
+    fn header(&mut self, _position: usize) {}
+    fn stored(&mut self, bytes: &[u8]) -> Result<(), String>;
+    // What is left of a stored block cut short by the end of the input.
+    fn truncated(&mut self, _bytes: &[u8]) {}
+    fn huffman(&mut self, decoder: &HuffmanDecoder, reader: &mut BitReader) -> Result<(), String>;
+}
+// Decodes blocks into `out`: the first `i` bytes are decoded and the first `checked` were given to `update`.
+pub(crate) struct Inflater<'a, O: Output + ?Sized, F: FnMut(&[u8])> {
+    out: &'a mut O,
+    update: F,
+    i: usize,
+    checked: usize,
+}
+// Encodes what is pushed block by block, which keeps memory bounded by the window and one block.
+pub(crate) struct Deflater<'a, F: MatchFinder> {
+    lzss: Lzss<'a, F>,
+    btype: BlockType,
+    writer: BitWriter,
+    chunk: Vec<u8>,
+}
+struct Tokens {
+    v_out: Vec<Code>,
+    pos: usize,
 }
 // Implementations.
-impl<'a> Reader<'a> {
-    fn new(v_in: &'a [u8]) -> Self {
+impl From<BlockType> for DeflateOptions {
+    fn from(btype: BlockType) -> Self {
         Self {
-            v_in,
-            last_read: 0,
-            offset: 32,
-            last_error: None,
-        }
-    }
-    fn read(&mut self) -> u8 {
-        if self.v_in.is_empty() {
-            self.last_error = Some(ERROR_BUFFER);
-            return 0;
-        }
-        let next = self.v_in[0];
-        self.v_in = &self.v_in[1..];
-        next
-    }
-    fn read_u16(&mut self) -> u16 {
-        if self.v_in.len() < 2 {
-            self.last_error = Some(ERROR_BUFFER);
-            return 0;
-        }
-        let mut array = [0; 2];
-        (&mut array).copy_from_slice(&self.v_in[0..2]);
-        let next = u16::from_le_bytes(array);
-        self.v_in = &self.v_in[2..];
-        next
-    }
-    fn read_bits(&mut self, width: u8) -> u16 {
-        let bits = self.peek_bits(width);
-        self.skip_bits(width);
-        bits
-    }
-    fn peek_bits(&mut self, width: u8) -> u16 {
-        while self.offset > 32 - width {
-            let next = self.read() as u32;
-            self.offset -= 8;
-            self.last_read >>= 8;
-            self.last_read |= next << 24;
-        }
-        let mut bits = self.last_read.wrapping_shr(self.offset as u32) as u16;
-        let mask = (1 << width) - 1;
-        bits &= mask;
-        bits
-    }
-    fn skip_bits(&mut self, width: u8) {
-        self.offset += width;
-    }
-    fn reset(&mut self) {
-        self.offset = 32;
-    }
-    fn get_code(&mut self, decoder: &[i32], max_width: u8) -> u16 {
-        let code = self.peek_bits(max_width);
-        let bits = Bits::from(decoder[code as usize]);
-        if bits.width > max_width {
-            self.last_error = Some(ERROR_WIDTH);
-            return 0;
-        }
-        self.skip_bits(bits.width);
-        bits.data
-    }
-    fn check_last_error(&self) -> Result<(), String> {
-        if let Some(e) = self.last_error {
-            return Err(e.into());
+            btype,
+            strategy: Strategy::default(),
         }
-        Ok(())
-    }
-    fn load_widthes(&mut self, code: u16, last: Option<u8>) -> Result<Box<IterU8>, String> {
-        Ok(match code {
-            0..=15 => Box::new(std::iter::once(code as u8)),
-            16 => {
-                let count = self.read_bits(2) + 3;
-                let last = match last {
-                    Some(x) => x,
-                    None => return Err(ERROR_PREVIOUS.into()),
-                };
-                Box::new(std::iter::repeat(last).take(count as usize))
-            }
-            17 => {
-                let zeros = self.read_bits(3) + 3;
-                Box::new(std::iter::repeat(0).take(zeros as usize))
-            }
-            18 => {
-                let zeros = self.read_bits(7) + 11;
-                Box::new(std::iter::repeat(0).take(zeros as usize))
-            }
-            _ => return Err(ERROR_WIDTHES.into()),
-        })
-    }
-    fn fill(&mut self, v_out: &mut Vec<u8>, len: usize) {
-        if self.v_in.len() < len {
-            self.last_error = Some(ERROR_BUFFER);
-            return;
-        }
-        v_out.reserve(len);
-        let mut array = vec![0; len];
-        (&mut array).copy_from_slice(&self.v_in[..len]);
-        v_out.extend(array);
-    }
-    fn fill_to(&mut self, v_out: &mut [u8], pos: usize, len: usize) {
-        if self.v_in.len() < len || v_out.len() < pos + len {
-            self.last_error = Some(ERROR_BUFFER);
-            return;
-        }
-        (&mut v_out[pos..pos + len]).copy_from_slice(&self.v_in[..len]);
     }
 }
-impl<'a> HuffmanDecoder<'a> {
-    fn new(
-        btype: BlockType,
-        reader: &mut Reader,
-        buf: &'a mut [i32; 0x10000],
-    ) -> Result<Self, String> {
-        Ok(match btype {
-            BlockType::Fixed => {
-                let max_lwidth = 9;
-                let max_dwidth = 5;
-                let (literal, buf) = buf.split_at_mut(1 << max_lwidth);
-                let (distance, _) = buf.split_at_mut(1 << max_dwidth);
-
-                // Fixed Huffman Tree
-                for i in 0..144 {
-                    let (data, width) = (0b0_0011_0000 + i as u16, 8);
-                    Self::set_mapping(literal, i, data, width, max_lwidth);
-                }
-                for (i, code) in (144..256).enumerate() {
-                    let (data, width) = (0b1_1001_0000 + i as u16, 9);
-                    Self::set_mapping(literal, code, data, width, max_lwidth);
-                }
-                for (i, code) in (256..280).enumerate() {
-                    let (data, width) = (0b0_0000_0000 + i as u16, 7);
-                    Self::set_mapping(literal, code, data, width, max_lwidth);
-                }
-                for (i, code) in (280..288).enumerate() {
-                    let (data, width) = (0b0_1100_0000 + i as u16, 8);
-                    Self::set_mapping(literal, code, data, width, max_lwidth);
-                }
-                for i in 0..30 {
-                    let (data, width) = (i as u16, 5);
-                    Self::set_mapping(distance, data, data, width, max_dwidth);
-                }
+impl<'a> DecodeTable<'a> {
+    pub(crate) fn new(
+        buf: &'a mut [i32],
+        widthes: &[u8],
+        max_bits: u8,
+        literals: bool,
+    ) -> (Self, &'a mut [i32]) {
+        // Variable initialization.
+        let max_width = widthes.iter().max().copied().unwrap_or(0);
+        let bits = cmp::min(max_width, max_bits);
+        let codes = canonical_codes(widthes)
+            .into_iter()
+            .map(|code| code.reverse())
+            .collect::<Vec<_>>();
+        let prefix_mask = (1 << bits) - 1;
 
-                Self {
-                    literal,
-                    distance,
-                    eob_width: 7,
-                    max_lwidth,
-                    max_dwidth,
-                }
-            }
-            BlockType::Dynamic => {
-                let lcount = reader.read_bits(5) as usize + 257;
-                let dcount = reader.read_bits(5) as usize + 1;
-                let wcount = reader.read_bits(4) as usize + 4;
-
-                // Width decoder.
-                let mut width_code_widthes = [0; 19];
-                for i in WIDTH_CODE_ORDER.iter().take(wcount) {
-                    width_code_widthes[*i] = reader.read_bits(3) as u8;
-                }
-                let (width_decoder, buf, max_wwidth, _) =
-                    Self::from_widthes(buf, &width_code_widthes);
-
-                // Literal.
-                let mut literal_code_widthes = Vec::with_capacity(lcount);
-                while literal_code_widthes.len() < lcount {
-                    let code = reader.get_code(width_decoder, max_wwidth);
-                    let last = literal_code_widthes.last().copied();
-                    literal_code_widthes.extend(reader.load_widthes(code, last)?);
-                }
+        // Subtables: codes longer than the primary table share their first
+        // `bits` bits, the subtable is indexed by the bits after them.
+        let mut sub_bits = [0u8; 1 << PRIMARY_BITS];
+        for code in codes.iter().filter(|code| code.width > bits) {
+            let prefix = code.data as usize & prefix_mask;
+            sub_bits[prefix] = cmp::max(sub_bits[prefix], code.width - bits);
+        }
+        let mut len = 1 << bits;
+        let mut offsets = [0usize; 1 << PRIMARY_BITS];
+        for (prefix, width) in sub_bits.iter().enumerate().filter(|(_, width)| **width > 0) {
+            offsets[prefix] = len;
+            len += 1 << width;
+        }
+        let (entries, buf) = buf.split_at_mut(len);
+        entries.fill(0);
 
-                // Distance.
-                let mut distance_code_widthes =
-                    literal_code_widthes.drain(lcount..).collect::<Vec<_>>();
-                distance_code_widthes.reserve(dcount);
-                while distance_code_widthes.len() < dcount {
-                    let code = reader.get_code(width_decoder, max_wwidth);
-                    let last = distance_code_widthes
-                        .last()
-                        .copied()
-                        .or_else(|| literal_code_widthes.last().copied());
-                    distance_code_widthes.extend(reader.load_widthes(code, last)?);
+        // Algorithm.
+        for (prefix, width) in sub_bits.iter().enumerate().filter(|(_, width)| **width > 0) {
+            let entry = ENTRY_SUBTABLE | (*width as u32) << 8 | (offsets[prefix] as u32) << 16;
+            entries[prefix] = entry as i32;
+        }
+        for (symbol, code) in codes.iter().enumerate().filter(|(_, code)| code.width > 0) {
+            let width = code.width as u32;
+            let entry = match literals && symbol < 256 {
+                true => ENTRY_LITERALS | (symbol as u32) << 8 | width << 24 | width,
+                false => ENTRY_SYMBOL | (symbol as u32) << 16 | width,
+            } as i32;
+            let data = code.data as usize;
+            if code.width <= bits {
+                for padding in 0..1 << (bits - code.width) {
+                    let index = padding << code.width | data;
+                    // Over-subscribed codes: the later symbol wins, as in one flat table.
+                    if sub_bits[index] > 0 {
+                        let offset = offsets[index];
+                        entries[offset..offset + (1 << sub_bits[index])].fill(entry);
+                    } else {
+                        entries[index] = entry;
+                    }
                 }
-                if distance_code_widthes.len() > dcount {
-                    return Err(ERROR_LENGTH.into());
+            } else {
+                let (offset, width) = (offsets[data & prefix_mask], code.width - bits);
+                for padding in 0..1 << (sub_bits[data & prefix_mask] - width) {
+                    entries[offset + (padding << width | data >> bits)] = entry;
                 }
-                let (literal, buf, max_lwidth, eob_width) =
-                    Self::from_widthes(buf, &literal_code_widthes);
-                let (distance, _, max_dwidth, _) = Self::from_widthes(buf, &distance_code_widthes);
-                Self {
-                    literal,
-                    distance,
-                    eob_width,
-                    max_lwidth,
-                    max_dwidth,
+            }
+        }
+        if literals {
+            // Two literals whose codes fit in the primary table together.
+            let mut singles = [0; 1 << PRIMARY_BITS];
+            singles[..1 << bits].copy_from_slice(&entries[..1 << bits]);
+            for (index, entry) in entries.iter_mut().take(1 << bits).enumerate() {
+                let first = singles[index] as u32;
+                let first_width = first & ENTRY_WIDTH;
+                let second = singles[index >> first_width] as u32;
+                let second_width = second & ENTRY_WIDTH;
+                let kinds = (first & ENTRY_KIND, second & ENTRY_KIND);
+                if kinds == (ENTRY_LITERALS, ENTRY_LITERALS)
+                    && first_width + second_width <= bits as u32
+                {
+                    let second_literal = (second >> 8) & 0xFF;
+                    *entry = (first + ENTRY_PAIR + (second_literal << 16) + second_width) as i32;
                 }
             }
-            _ => unimplemented!(),
-        })
+        }
+        (Self { entries, bits }, buf)
     }
-    fn set_mapping(decoder: &mut [i32], code: u16, data: u16, width: u8, max_width: u8) {
-        let bits = Bits { data, width }.reverse();
-        for padding in 0..(1 << (max_width - width)) {
-            decoder[padding << width | bits.data as usize] = Bits { data: code, width }.as_i32();
+    #[inline(always)]
+    fn lookup(&self, reader: &mut BitReader) -> u32 {
+        let entry = self.entries[reader.peek_bits(self.bits) as usize] as u32;
+        if entry & ENTRY_KIND != ENTRY_SUBTABLE {
+            return entry;
         }
+        let sub_bits = (entry >> 8) as u8;
+        let index = reader.peek_bits(self.bits + sub_bits) >> self.bits;
+        self.entries[(entry >> 16) as usize + index as usize] as u32
     }
-    fn from_widthes(buf: &'a mut [i32], widthes: &[u8]) -> (&'a mut [i32], &'a mut [i32], u8, u8) {
-        let max_width = *widthes.iter().max().unwrap_or(&0);
-        let (decoder, buf) = buf.split_at_mut(1 << max_width);
-        let eob_width = Self::restore_canonical_huffman_codes(decoder, &widthes, max_width as u8);
-        (decoder, buf, max_width, eob_width)
+    #[inline(always)]
+    fn entry(&self, bits: u64) -> u32 {
+        let entry = self.entries[(bits & ((1 << self.bits) - 1)) as usize] as u32;
+        if entry & ENTRY_KIND != ENTRY_SUBTABLE {
+            return entry;
+        }
+        let index = (bits >> self.bits) & ((1 << (entry >> 8 & 0xFF)) - 1);
+        self.entries[(entry >> 16) as usize + index as usize] as u32
     }
-    fn restore_canonical_huffman_codes(width: &mut [i32], widthes: &[u8], max_width: u8) -> u8 {
-        let mut codes = widthes
-            .iter()
-            .enumerate()
-            .filter(|(_, code_width)| **code_width > 0)
-            .map(|(code, code_width)| (code as u16, *code_width))
-            .collect::<Vec<_>>();
-        codes.sort_by_key(|x| x.1);
-
-        let mut code = 0;
-        let mut prev_width = 0;
-        let mut eob_width = 0;
-        for (c, w) in codes {
-            code <<= w - prev_width;
-            Self::set_mapping(width, c, code, w, max_width);
-            if c == END_OF_BLOCK {
-                eob_width = w;
+    #[inline(always)]
+    fn symbol(entry: u32, reader: &mut BitReader) -> Result<u16, String> {
+        match entry & ENTRY_KIND {
+            ENTRY_SYMBOL => {
+                reader.skip_bits((entry & ENTRY_WIDTH) as u8)?;
+                Ok((entry >> 16) as u16)
             }
-            code += 1;
-            prev_width = w;
-        }
-        eob_width
-    }
-    fn decode(&self, reader: &mut Reader) -> Code {
-        let code = reader.peek_bits(self.eob_width);
-        let mut bits = Bits::from(self.literal[code as usize]);
-        if bits.width > self.eob_width {
-            let code = reader.peek_bits(self.max_lwidth);
-            bits = Bits::from(self.literal[code as usize]);
-            if bits.width > self.max_lwidth {
-                reader.last_error = Some(ERROR_WIDTH);
-                return Code::EndOfBlock;
+            ENTRY_LITERALS => {
+                reader.skip_bits((entry >> 24) as u8)?;
+                Ok((entry >> 8) as u8 as u16)
             }
+            _ => Err(ERROR_WIDTH.into()),
         }
-        reader.skip_bits(bits.width);
-
-        match bits.data {
-            0..=255 => Code::Literal(bits.data as u8),
+    }
+    pub(crate) fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        Self::symbol(self.lookup(reader), reader)
+    }
+}
+impl<'a> HuffmanDecoder<'a> {
+    pub(crate) fn fixed() -> Self {
+        let (entries, len, bits, dbits) = FIXED_TABLES.get_or_init(|| {
+            // Fixed Huffman Tree
+            let mut lwidthes = [8; 288];
+            lwidthes[144..256].fill(9);
+            lwidthes[256..280].fill(7);
+            let mut entries = vec![0; 0x1000];
+            let decoder = HuffmanDecoder::dynamic(&mut entries, &lwidthes, &[5; 32]);
+            let (len, dlen) = (
+                decoder.literal.entries.len(),
+                decoder.distance.entries.len(),
+            );
+            let (bits, dbits) = (decoder.literal.bits, decoder.distance.bits);
+            entries.truncate(len + dlen);
+            (entries, len, bits, dbits)
+        });
+        let literal = DecodeTable {
+            entries: &entries[..*len],
+            bits: *bits,
+        };
+        let distance = DecodeTable {
+            entries: &entries[*len..],
+            bits: *dbits,
+        };
+        Self { literal, distance }
+    }
+    pub(crate) fn dynamic(buf: &'a mut [i32], lwidthes: &[u8], dwidthes: &[u8]) -> Self {
+        let (literal, buf) = DecodeTable::new(buf, lwidthes, PRIMARY_BITS, true);
+        let (distance, _) = DecodeTable::new(buf, dwidthes, PRIMARY_DBITS, false);
+        Self { literal, distance }
+    }
+    pub(crate) fn decode(&self, reader: &mut BitReader) -> Result<Code, String> {
+        let symbol = self.literal.decode(reader)?;
+        self.code(symbol, reader)
+    }
+    #[inline(always)]
+    pub(crate) fn decode_literals(&self, reader: &mut BitReader) -> Result<Decoded, String> {
+        // One lookup gives two literals when their codes are short.
+        let entry = self.literal.lookup(reader);
+        // At the end of the input, the second one may be missing while the first is there.
+        if entry & ENTRY_KIND == ENTRY_LITERALS
+            && reader.skip_bits((entry & ENTRY_WIDTH) as u8).is_ok()
+        {
+            let literals = [(entry >> 8) as u8, (entry >> 16) as u8];
+            return Ok(Decoded::Literals(
+                literals,
+                1 + (entry & ENTRY_PAIR > 0) as usize,
+            ));
+        }
+        let symbol = DecodeTable::symbol(entry, reader)?;
+        Ok(Decoded::Code(self.code(symbol, reader)?))
+    }
+    fn code(&self, symbol: u16, reader: &mut BitReader) -> Result<Code, String> {
+        Ok(match symbol {
+            0..=255 => Code::Literal(symbol as u8),
             256 => Code::EndOfBlock,
             257..=285 => {
-                let (code_base_length, width_length) = LENGTH_TABLE[bits.data as usize - 257];
-                let bits_length = reader.read_bits(width_length) as u8;
+                let (code_base_length, width_length) = LENGTH_TABLE[symbol as usize - 257];
+                let bits_length = reader.read_bits(width_length)? as u8;
 
-                let code = reader.get_code(self.distance, self.max_dwidth);
+                let code = self.distance.decode(reader)?;
 
-                let (code_base_distance, width_distance) = DISTANCE_TABLE[code as usize];
-                let bits_distance = reader.read_bits(width_distance);
+                let (code_base_distance, width_distance) = match DISTANCE_TABLE.get(code as usize) {
+                    Some(x) => *x,
+                    None if code < 32 => return Err(ERROR_DISTANCE_CODE.into()),
+                    None => return Err(ERROR_VALUE.into()),
+                };
+                let bits_distance = reader.read_bits(width_distance)? as u16;
 
                 Code::Pointer {
                     length: code_base_length + bits_length,
                     distance: code_base_distance + bits_distance,
                 }
             }
-            _ => {
-                reader.last_error = Some(ERROR_VALUE);
-                Code::EndOfBlock
+            286 | 287 => return Err(ERROR_LENGTH_CODE.into()),
+            _ => return Err(ERROR_VALUE.into()),
+        })
+    }
+}
+impl Output for Vec<u8> {
+    fn buffer(&mut self, len: usize) -> &mut [u8] {
+        if self.len() < len {
+            let len = cmp::max(len, 2 * self.len());
+            self.resize(len, 0);
+        }
+        self
+    }
+}
+impl Output for [u8] {
+    fn buffer(&mut self, _len: usize) -> &mut [u8] {
+        self
+    }
+}
+impl Output for Window {
+    fn buffer(&mut self, len: usize) -> &mut [u8] {
+        self.0.buffer(len)
+    }
+    fn slide(&mut self, i: usize) -> usize {
+        let window = MAX_DISTANCE as usize;
+        if i < 2 * window {
+            return i;
+        }
+        self.0.copy_within(i - window..i, 0);
+        window
+    }
+}
+impl<'a, O: Output + ?Sized, F: FnMut(&[u8])> Inflater<'a, O, F> {
+    pub(crate) fn new(out: &'a mut O, i: usize, update: F) -> Self {
+        Self {
+            out,
+            update,
+            i,
+            checked: i,
+        }
+    }
+    pub(crate) fn len(&self) -> usize {
+        self.i
+    }
+    // Decoded bytes are given to `update` by windows, while they are still in cache.
+    fn flush(&mut self) {
+        (self.update)(&self.out.buffer(self.i)[self.checked..self.i]);
+        self.i = self.out.slide(self.i);
+        self.checked = self.i;
+    }
+}
+impl<'a, O: Output + ?Sized, F: FnMut(&[u8])> Sink for Inflater<'a, O, F> {
+    fn stored(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let i = self.i;
+        let v_out = self.out.buffer(i + bytes.len());
+        if v_out.len() < i + bytes.len() {
+            return Err(ERROR_BUFFER.into());
+        }
+        v_out[i..i + bytes.len()].copy_from_slice(bytes);
+        self.i += bytes.len();
+        self.flush();
+        Ok(())
+    }
+    fn huffman(&mut self, decoder: &HuffmanDecoder, reader: &mut BitReader) -> Result<(), String> {
+        loop {
+            let v_out = self.out.buffer(self.i + FAST_MARGIN);
+            let end = cmp::min(v_out.len(), self.checked + UPDATE_LENGTH + FAST_MARGIN);
+            let (i, end_of_block) = inflate_fast(decoder, reader, &mut v_out[..end], self.i);
+            self.i = i;
+            if end_of_block {
+                break;
+            }
+            if self.i - self.checked >= UPDATE_LENGTH {
+                self.flush();
+                continue;
+            }
+            // Near the end of the input or of the output, one symbol at a time.
+            let (literals, len) = match decoder.decode_literals(reader)? {
+                Decoded::Literals(literals, len) => (literals, len),
+                Decoded::Code(Code::Literal(a)) => ([a, 0], 1),
+                Decoded::Code(Code::EndOfBlock) => break,
+                Decoded::Code(Code::Pointer {
+                    distance: d,
+                    length: l,
+                }) => {
+                    let l = l as usize + 3;
+                    extend_to(self.out.buffer(self.i + l), self.i, d as usize, l)?;
+                    self.i += l;
+                    continue;
+                }
+            };
+            let v_out = self.out.buffer(self.i + len);
+            if v_out.len() < self.i + len {
+                return Err(ERROR_BUFFER.into());
+            }
+            v_out[self.i..self.i + len].copy_from_slice(&literals[..len]);
+            self.i += len;
+        }
+        self.flush();
+        Ok(())
+    }
+}
+impl<'a, F: MatchFinder> Deflater<'a, F> {
+    pub(crate) fn new(lzss: Lzss<'a, F>, btype: BlockType) -> Self {
+        Self {
+            lzss,
+            btype,
+            writer: BitWriter::new(),
+            chunk: Vec::with_capacity(BLOCK_LENGTH),
+        }
+    }
+    pub(crate) fn push(&mut self, mut v_in: &[u8]) {
+        while !v_in.is_empty() {
+            let len = cmp::min(v_in.len(), BLOCK_LENGTH - self.chunk.len());
+            self.chunk.extend_from_slice(&v_in[..len]);
+            v_in = &v_in[len..];
+            if self.chunk.len() == BLOCK_LENGTH {
+                self.write(false);
             }
         }
     }
+    // The bytes of the blocks written so far.
+    pub(crate) fn take_bytes(&mut self) -> Vec<u8> {
+        self.writer.take_bytes()
+    }
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        self.write(true);
+        self.writer.finish()
+    }
+    fn write(&mut self, bfinal: bool) {
+        let (chunk, btype) = (&self.chunk[..], self.btype);
+        if btype == BlockType::Raw {
+            let mut v_out = Vec::with_capacity(chunk.len() + 10);
+            stored_encode(&mut v_out, chunk, bfinal);
+            self.writer.write_bytes(&v_out);
+        } else {
+            let tokens = self.lzss.encode(chunk, bfinal);
+            self.writer.reserve(deflate_bound(chunk.len(), btype));
+            write_block(&mut self.writer, tokens, btype, bfinal);
+        }
+        self.chunk.clear();
+    }
+}
+impl Sink for Tokens {
+    fn stored(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.v_out.extend(bytes.iter().map(|x| Code::Literal(*x)));
+        self.v_out.push(Code::EndOfBlock);
+        self.pos += bytes.len();
+        Ok(())
+    }
+    fn huffman(&mut self, decoder: &HuffmanDecoder, reader: &mut BitReader) -> Result<(), String> {
+        let v_out = &mut self.v_out;
+        self.pos = decode_codes(decoder, reader, self.pos, |code| v_out.push(code))?;
+        v_out.push(Code::EndOfBlock);
+        Ok(())
+    }
 }
 // Functions.
+fn check_widthes(widthes: &[u8], single: bool) -> Result<(), String> {
+    // Kraft's sum, in units of the longest code.
+    let (count, kraft) = widthes
+        .iter()
+        .filter(|width| **width > 0)
+        .fold((0, 0), |(count, kraft), width| {
+            (count + 1, kraft + (1u32 << (MAX_WIDTH - width)))
+        });
+    if kraft > 1 << MAX_WIDTH {
+        return Err(ERROR_OVERSUBSCRIBED.into());
+    }
+    // As zlib, a lone code of width 1 is accepted.
+    let lone = single && count == 1 && kraft == 1 << (MAX_WIDTH - 1);
+    if count > 0 && kraft < 1 << MAX_WIDTH && !lone {
+        return Err(ERROR_INCOMPLETE.into());
+    }
+    Ok(())
+}
+// Counts of literal, distance and width codes, then the widthes of the width codes.
+fn read_width_code(
+    reader: &mut BitReader,
+    strict: bool,
+) -> Result<(usize, usize, usize, [u8; 19]), String> {
+    let lcount = reader.read_bits(5)? as usize + 257;
+    let dcount = reader.read_bits(5)? as usize + 1;
+    let wcount = reader.read_bits(4)? as usize + 4;
+    if strict && (lcount > MAX_LCOUNT || dcount > MAX_DCOUNT) {
+        return Err(ERROR_COUNT.into());
+    }
+    let mut width_code_widthes = [0; 19];
+    for i in WIDTH_CODE_ORDER.iter().take(wcount) {
+        width_code_widthes[*i] = reader.read_bits(3)? as u8;
+    }
+    if strict {
+        check_widthes(&width_code_widthes, false)?;
+    }
+    Ok((lcount, dcount, wcount, width_code_widthes))
+}
+pub(crate) fn read_widthes(
+    reader: &mut BitReader,
+    buf: &mut [i32],
+    strict: bool,
+) -> Result<Widthes, String> {
+    // Width decoder.
+    let (lcount, dcount, wcount, width_code_widthes) = read_width_code(reader, strict)?;
+    let (width_decoder, _) = DecodeTable::new(buf, &width_code_widthes, MAX_WWIDTH, false);
+
+    // Literal.
+    let mut literal_code_widthes = Vec::with_capacity(lcount);
+    while literal_code_widthes.len() < lcount {
+        let code = width_decoder.decode(reader)?;
+        let last = literal_code_widthes.last().copied();
+        literal_code_widthes.extend(load_widthes(reader, code, last)?);
+    }
+
+    // Distance.
+    let mut distance_code_widthes = literal_code_widthes.drain(lcount..).collect::<Vec<_>>();
+    distance_code_widthes.reserve(dcount);
+    while distance_code_widthes.len() < dcount {
+        let code = width_decoder.decode(reader)?;
+        let last = distance_code_widthes
+            .last()
+            .copied()
+            .or_else(|| literal_code_widthes.last().copied());
+        distance_code_widthes.extend(load_widthes(reader, code, last)?);
+    }
+    if distance_code_widthes.len() > dcount {
+        return Err(ERROR_LENGTH.into());
+    }
+    if strict {
+        if literal_code_widthes[END_OF_BLOCK as usize] == 0 {
+            return Err(ERROR_END_OF_BLOCK.into());
+        }
+        check_widthes(&literal_code_widthes, true)?;
+        check_widthes(&distance_code_widthes, true)?;
+    }
+    Ok((
+        wcount,
+        width_code_widthes,
+        literal_code_widthes,
+        distance_code_widthes,
+    ))
+}
+fn load_widthes(
+    reader: &mut BitReader,
+    code: u16,
+    last: Option<u8>,
+) -> Result<Box<IterU8>, String> {
+    Ok(match code {
+        0..=15 => Box::new(std::iter::once(code as u8)),
+        16 => {
+            let count = reader.read_bits(2)? + 3;
+            let last = match last {
+                Some(x) => x,
+                None => return Err(ERROR_PREVIOUS.into()),
+            };
+            Box::new(std::iter::repeat_n(last, count as usize))
+        }
+        17 => {
+            let zeros = reader.read_bits(3)? + 3;
+            Box::new(std::iter::repeat_n(0, zeros as usize))
+        }
+        18 => {
+            let zeros = reader.read_bits(7)? + 11;
+            Box::new(std::iter::repeat_n(0, zeros as usize))
+        }
+        _ => return Err(ERROR_WIDTHES.into()),
+    })
+}
+fn read_stored_len(reader: &mut BitReader) -> Result<usize, String> {
+    reader.align();
+    let len = reader.read_bits(16)? as u16;
+    let nlen = reader.read_bits(16)? as u16;
+    if !len != nlen {
+        return Err(ERROR_COMPLEMENT.into());
+    }
+    Ok(len as usize)
+}
+// Fails as `read_block` would on the header of a block, or on a stored block which doesn't fit in the input, but
+// without building any table.
+pub(crate) fn check_header(reader: &mut BitReader, strict: bool) -> Result<(), String> {
+    reader.read_bits(1)?;
+    match reader.read_bits(2)? {
+        0b11 => return Err(ERROR_RESERVED.into()),
+        0b00 => {
+            let len = read_stored_len(reader)?;
+            reader.read_bytes(len)?;
+        }
+        0b01 => (),
+        _ => {
+            read_width_code(reader, strict)?;
+        }
+    }
+    Ok(())
+}
+// Reads the header of one block and gives it to `sink`, then its data, and gives back BFINAL.
+pub(crate) fn read_block(
+    reader: &mut BitReader,
+    buf: &mut [i32; 0x10000],
+    strict: bool,
+    sink: &mut impl Sink,
+) -> Result<bool, String> {
+    let bfinal = reader.read_bits(1)? == 1;
+    let btype = reader.read_bits(2)?;
+    if btype == 0b11 {
+        sink.block(bfinal, None);
+        return Err(ERROR_RESERVED.into());
+    }
+    let btype = BlockType::from(btype as u16);
+    sink.block(bfinal, Some(btype));
+    let decoder = match btype {
+        BlockType::Raw => {
+            let len = read_stored_len(reader)?;
+            sink.header(reader.position());
+            let available = cmp::min(len, reader.available());
+            let stored = reader.read_bytes(available)?;
+            if available < len {
+                sink.truncated(stored);
+                reader.read_bytes(len - available)?;
+            }
+            sink.stored(stored)?;
+            return Ok(bfinal);
+        }
+        BlockType::Fixed => HuffmanDecoder::fixed(),
+        BlockType::Dynamic => {
+            let widthes = read_widthes(reader, buf, strict)?;
+            sink.widthes(&widthes);
+            HuffmanDecoder::dynamic(buf, &widthes.2, &widthes.3)
+        }
+    };
+    sink.header(reader.position());
+    sink.huffman(&decoder, reader)?;
+    Ok(bfinal)
+}
+pub(crate) fn read_blocks(
+    reader: &mut BitReader,
+    buf: &mut [i32; 0x10000],
+    strict: bool,
+    sink: &mut impl Sink,
+) -> Result<(), String> {
+    while !read_block(reader, buf, strict, sink)? {}
+    Ok(())
+}
+// Decodes the codes of a Huffman block up to its end-of-block code, which is not given to `f`, and gives back the
+// position reached from `pos`.
+pub(crate) fn decode_codes(
+    decoder: &HuffmanDecoder,
+    reader: &mut BitReader,
+    mut pos: usize,
+    mut f: impl FnMut(Code),
+) -> Result<usize, String> {
+    loop {
+        let code = decoder.decode(reader)?;
+        match code {
+            Code::EndOfBlock => return Ok(pos),
+            Code::Literal(_) => pos += 1,
+            Code::Pointer { distance, length } => {
+                if distance as usize > pos {
+                    return Err(ERROR_POSITION.into());
+                }
+                pos += length as usize + 3;
+            }
+        }
+        f(code);
+    }
+}
 fn extend_to(buf: &mut [u8], mut pos: usize, mut d: usize, mut l: usize) -> Result<(), String> {
     if pos < d {
         return Err(ERROR_POSITION.into());
@@ -365,7 +822,7 @@ fn extend_to(buf: &mut [u8], mut pos: usize, mut d: usize, mut l: usize) -> Resu
 
     // Copy bytes fastly
     while l >= d {
-        let (left, right) = (&mut buf[start..pos + d]).split_at_mut(d);
+        let (left, right) = buf[start..pos + d].split_at_mut(d);
         right.copy_from_slice(left);
         pos += d;
         l -= d;
@@ -373,115 +830,505 @@ fn extend_to(buf: &mut [u8], mut pos: usize, mut d: usize, mut l: usize) -> Resu
     }
 
     // Copy the last remaining bytes
-    let (left, right) = (&mut buf[start..pos + l]).split_at_mut(d);
+    let (left, right) = buf[start..pos + l].split_at_mut(d);
     right.copy_from_slice(&left[..l]);
     Ok(())
 }
+// Copies a match which is known to fit, a byte or a word at a time.
+#[inline(always)]
+fn copy_match(buf: &mut [u8], pos: usize, d: usize, l: usize) {
+    let start = pos - d;
+    if d == 1 {
+        let x = buf[start];
+        buf[pos..pos + l].fill(x);
+    } else if d >= 8 && l >= 8 {
+        // Bounds are checked once by the slice. A word is only read once it is written, and the last one ends the match.
+        let ptr = buf[..pos + l].as_mut_ptr();
+        let mut k = 0;
+        unsafe {
+            while k + 8 <= l {
+                let word = std::ptr::read_unaligned(ptr.add(start + k) as *const u64);
+                std::ptr::write_unaligned(ptr.add(pos + k) as *mut u64, word);
+                k += 8;
+            }
+            let word = std::ptr::read_unaligned(ptr.add(start + l - 8) as *const u64);
+            std::ptr::write_unaligned(ptr.add(pos + l - 8) as *mut u64, word);
+        }
+    } else {
+        for k in 0..l {
+            buf[pos + k] = buf[start + k];
+        }
+    }
+}
+fn check_tokens(v_in: &[Code]) -> Result<(), String> {
+    let mut pos = 0;
+    for code in v_in {
+        match *code {
+            Code::EndOfBlock => (),
+            Code::Literal(_) => pos += 1,
+            Code::Pointer { distance, length } => {
+                if distance == 0 || distance > MAX_DISTANCE {
+                    return Err(ERROR_DISTANCE.into());
+                }
+                if distance as usize > pos {
+                    return Err(ERROR_POSITION.into());
+                }
+                pos += length as usize + 3;
+            }
+        }
+    }
+    Ok(())
+}
+fn read_chunk(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize, String> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(error) if error.kind() == ErrorKind::Interrupted => (),
+            Err(error) => return Err(error.to_string()),
+        }
+    }
+    Ok(len)
+}
+pub(crate) fn copy_to(v_in: &[u8], v_out: &mut [u8]) -> Result<usize, String> {
+    if v_in.len() > v_out.len() {
+        return Err(ERROR_BUFFER.into());
+    }
+    v_out[..v_in.len()].copy_from_slice(v_in);
+    Ok(v_in.len())
+}
+// Copies `v_in` at `*pos`, failing as soon as it does not fit.
+pub(crate) fn copy_at(v_in: &[u8], v_out: &mut [u8], pos: &mut usize) -> Result<(), String> {
+    let len = copy_to(v_in, &mut v_out[*pos..])?;
+    *pos += len;
+    Ok(())
+}
+pub(crate) fn write_all(writer: &mut impl Write, buf: &[u8]) -> Result<(), String> {
+    writer.write_all(buf).map_err(|error| error.to_string())
+}
+pub(crate) fn deflate_read(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    options: DeflateOptions,
+    cache: &mut Cache,
+    mut update: impl FnMut(&[u8]),
+) -> Result<usize, String> {
+    // Memory is bounded by the window and one block, whatever the input size.
+    let mut deflater = Deflater::new(Lzss::new(cache, options.strategy), options.btype);
+    let mut chunk = vec![0; BLOCK_LENGTH];
+    let mut len = 0;
+    loop {
+        let chunk_len = read_chunk(reader, &mut chunk)?;
+        update(&chunk[..chunk_len]);
+        deflater.push(&chunk[..chunk_len]);
+        len += chunk_len;
+        if chunk_len < BLOCK_LENGTH {
+            write_all(writer, &deflater.finish())?;
+            return Ok(len);
+        }
+        write_all(writer, &deflater.take_bytes())?;
+    }
+}
+fn stored_encode(v_out: &mut Vec<u8>, v_in: &[u8], bfinal: bool) {
+    let mut chunks = v_in.chunks(MAX_STORED_LENGTH).peekable();
+    if chunks.peek().is_none() {
+        v_out.extend(&[bfinal as u8, 0x00, 0x00, 0xFF, 0xFF]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let len = chunk.len() as u16;
+        v_out.push((bfinal && chunks.peek().is_none()) as u8);
+        v_out.extend(&len.to_le_bytes());
+        v_out.extend(&(!len).to_le_bytes());
+        v_out.extend(chunk);
+    }
+}
+pub(crate) fn deflate_into(
+    v_in: &[u8],
+    options: DeflateOptions,
+    cache: &mut Cache,
+    v_out: &mut Vec<u8>,
+    mut stats: Option<&mut Stats>,
+    mut update: impl FnMut(&[u8]),
+) {
+    // Each chunk is given to `update` just before it is encoded, while it is still in cache.
+    let btype = options.btype;
+    if btype == BlockType::Raw {
+        v_out.reserve(deflate_bound(v_in.len(), btype));
+        let mut chunks = v_in.chunks(MAX_STORED_LENGTH).peekable();
+        loop {
+            let chunk = chunks.next().unwrap_or_default();
+            let bfinal = chunks.peek().is_none();
+            update(chunk);
+            stored_encode(v_out, chunk, bfinal);
+            if let Some(stats) = stats.as_deref_mut() {
+                stats.literals += chunk.len();
+                stats.blocks.push(BlockStats {
+                    header_size: 40,
+                    payload_size: chunk.len() * 8,
+                    literals: chunk.len(),
+                    ..BlockStats::new(btype)
+                });
+            }
+            if bfinal {
+                return;
+            }
+        }
+    }
+    let mut lzss = Lzss::new(cache, options.strategy);
+    let mut writer = BitWriter::from_vec(mem::take(v_out));
+    let mut chunks = v_in.chunks(BLOCK_LENGTH).peekable();
+    loop {
+        let chunk = chunks.next().unwrap_or_default();
+        let bfinal = chunks.peek().is_none();
+        update(chunk);
+        let start = Instant::now();
+        let tokens = lzss.encode(chunk, bfinal);
+        let lzss_time = start.elapsed();
+        let start = Instant::now();
+        writer.reserve(deflate_bound(chunk.len(), btype));
+        let block_stats = write_block(&mut writer, tokens, btype, bfinal);
+        if let Some(stats) = stats.as_deref_mut() {
+            stats.lzss_time += lzss_time;
+            stats.huffman_time += start.elapsed();
+            stats.blocks.push(block_stats);
+            stats.count_tokens(tokens);
+        }
+        if bfinal {
+            *v_out = writer.finish();
+            return;
+        }
+    }
+}
+pub(crate) fn deflate_slice(
+    v_in: &[u8],
+    options: DeflateOptions,
+    cache: &mut Cache,
+    v_out: &mut [u8],
+    pos: &mut usize,
+    mut update: impl FnMut(&[u8]),
+) -> Result<(), String> {
+    // Each block goes to the caller's buffer once written, only one is held in cache.
+    let btype = options.btype;
+    if btype == BlockType::Raw {
+        let mut chunks = v_in.chunks(MAX_STORED_LENGTH).peekable();
+        loop {
+            let chunk = chunks.next().unwrap_or_default();
+            let bfinal = chunks.peek().is_none();
+            update(chunk);
+            let len = chunk.len() as u16;
+            copy_at(&[bfinal as u8], v_out, pos)?;
+            copy_at(&len.to_le_bytes(), v_out, pos)?;
+            copy_at(&(!len).to_le_bytes(), v_out, pos)?;
+            copy_at(chunk, v_out, pos)?;
+            if bfinal {
+                return Ok(());
+            }
+        }
+    }
+    let mut bytes = mem::take(&mut cache.bytes);
+    bytes.clear();
+    let mut writer = BitWriter::from_vec(bytes);
+    let mut lzss = Lzss::new(cache, options.strategy);
+    let mut chunks = v_in.chunks(BLOCK_LENGTH).peekable();
+    let result = loop {
+        let chunk = chunks.next().unwrap_or_default();
+        let bfinal = chunks.peek().is_none();
+        update(chunk);
+        let tokens = lzss.encode(chunk, bfinal);
+        writer.reserve(deflate_bound(chunk.len(), btype));
+        write_block(&mut writer, tokens, btype, bfinal);
+        if bfinal {
+            writer.align();
+        }
+        let result = writer.drain_bytes(|bytes| copy_at(bytes, v_out, pos));
+        if result.is_err() || bfinal {
+            break result;
+        }
+    };
+    cache.bytes = writer.finish();
+    result
+}
 // Main functions.
-pub fn deflate(v_in: &[u8], btype: BlockType, cache: &mut Cache) -> Vec<u8> {
-    // Variable Initialization.
-    let buf = cache.inner_mut();
-    // Algorithm.
-    let mut encoded = lzss_encode(v_in, buf);
-    encoded.push(Code::EndOfBlock);
-    huffman_encode(&encoded, btype, buf)
+pub fn deflate(v_in: &[u8], options: impl Into<DeflateOptions>, cache: &mut Cache) -> Vec<u8> {
+    let mut v_out = Vec::new();
+    deflate_append(v_in, options, cache, &mut v_out);
+    v_out
 }
 
-pub fn inflate(v_in: &[u8], cache: &mut Cache) -> Result<Vec<u8>, String> {
-    // Variable Initialization.
-    let buf = cache.inner_mut();
-    let mut reader = Reader::new(v_in);
-    let mut bfinal = 0;
+pub fn deflate_append(
+    v_in: &[u8],
+    options: impl Into<DeflateOptions>,
+    cache: &mut Cache,
+    v_out: &mut Vec<u8>,
+) {
+    deflate_into(v_in, options.into(), cache, v_out, None, |_| ())
+}
+
+pub fn deflate_to(
+    v_in: &[u8],
+    options: impl Into<DeflateOptions>,
+    cache: &mut Cache,
+    v_out: &mut [u8],
+) -> Result<usize, String> {
+    let mut pos = 0;
+    deflate_slice(v_in, options.into(), cache, v_out, &mut pos, |_| ())?;
+    Ok(pos)
+}
+
+pub fn deflate_bound(len: usize, options: impl Into<DeflateOptions>) -> usize {
+    // A dynamic block's codes are optimal, so never longer than the fixed ones
+    // (9 bits at most), one bit of slack stands for the forced second code.
+    match options.into().btype {
+        BlockType::Raw => len + 5 * cmp::max(1, len.div_ceil(MAX_STORED_LENGTH)),
+        BlockType::Fixed => {
+            let blocks = cmp::max(1, len.div_ceil(BLOCK_LENGTH));
+            (9 * len + 10 * blocks).div_ceil(8)
+        }
+        BlockType::Dynamic => {
+            let blocks = cmp::max(1, len.div_ceil(BLOCK_LENGTH));
+            let header = 3 + 14 + 3 * 19 + 7 * (MAX_LCOUNT + MAX_DCOUNT);
+            (9 * len + (header + 16) * blocks).div_ceil(8)
+        }
+    }
+}
+
+pub fn deflate_stats(
+    v_in: &[u8],
+    options: impl Into<DeflateOptions>,
+    cache: &mut Cache,
+) -> (Vec<u8>, Stats) {
+    // Variable initialization.
     let mut v_out = Vec::new();
+    let mut stats = Stats::new();
 
-    // Algorithms.
-    while bfinal == 0 {
-        bfinal = reader.read_bits(1);
-        let btype = reader.read_bits(2);
-        reader.check_last_error()?;
-        match btype {
-            0b11 => return Err(ERROR_RESERVED.into()),
-            0b00 => {
-                reader.reset();
-                let len = reader.read_u16();
-                let nlen = reader.read_u16();
-                if !len != nlen {
-                    return Err(ERROR_COMPLEMENT.into());
-                }
-                reader.fill(&mut v_out, len as usize);
-            }
-            btype => {
-                let decoder = HuffmanDecoder::new(BlockType::from(btype), &mut reader, buf)?;
-                reader.check_last_error()?;
-                loop {
-                    let x = decoder.decode(&mut reader);
-                    match x {
-                        Code::EndOfBlock => break,
-                        Code::Literal(a) => v_out.push(a),
-                        Code::Pointer {
-                            distance: d,
-                            length: l,
-                        } => extend(&mut v_out, d as usize, l as usize + 3)?,
-                    }
+    // Algorithm.
+    deflate_into(
+        v_in,
+        options.into(),
+        cache,
+        &mut v_out,
+        Some(&mut stats),
+        |_| (),
+    );
+    (v_out, stats)
+}
+
+pub fn deflate_stream(
+    mut reader: impl Read,
+    mut writer: impl Write,
+    options: impl Into<DeflateOptions>,
+    cache: &mut Cache,
+) -> Result<usize, String> {
+    deflate_read(&mut reader, &mut writer, options.into(), cache, |_| ())
+}
+
+pub fn encode_tokens(v_in: &[Code], btype: BlockType) -> Result<Vec<u8>, String> {
+    // Conditions.
+    check_tokens(v_in)?;
+
+    // Algorithm.
+    if btype != BlockType::Raw {
+        let tokens = v_in
+            .iter()
+            .map(|code| Token::from(*code))
+            .collect::<Vec<_>>();
+        return Ok(huffman_encode(&tokens, btype));
+    }
+    let v_in = match v_in.last() {
+        Some(Code::EndOfBlock) => &v_in[..v_in.len() - 1],
+        _ => v_in,
+    };
+    let mut blocks = v_in.split(|code| *code == Code::EndOfBlock).peekable();
+    let mut decoded = Vec::new();
+    let mut v_out = Vec::new();
+    while let Some(block) = blocks.next() {
+        let start = decoded.len();
+        for code in block {
+            match *code {
+                Code::Literal(a) => decoded.push(a),
+                Code::Pointer { distance, length } => {
+                    extend(&mut decoded, distance as usize, length as usize + 3)?
                 }
+                Code::EndOfBlock => (),
             }
         }
+        stored_encode(&mut v_out, &decoded[start..], blocks.peek().is_none());
     }
-    reader.check_last_error()?;
     Ok(v_out)
 }
 
+pub fn inflate(v_in: &[u8], cache: &mut Cache) -> Result<Vec<u8>, String> {
+    inflate_with(v_in, InflateOptions::default(), cache)
+}
+
+pub fn inflate_with(
+    v_in: &[u8],
+    options: InflateOptions,
+    cache: &mut Cache,
+) -> Result<Vec<u8>, String> {
+    Ok(inflate_len(v_in, options, cache, |_| ())?.0)
+}
+
+pub(crate) fn inflate_len(
+    v_in: &[u8],
+    options: InflateOptions,
+    cache: &mut Cache,
+    update: impl FnMut(&[u8]),
+) -> Result<(Vec<u8>, usize), String> {
+    let mut v_out = Vec::new();
+    let (len, consumed) = inflate_into(v_in, options, cache, &mut v_out, update)?;
+    v_out.truncate(len);
+    Ok((v_out, consumed))
+}
+
 pub fn inflate_to(v_in: &[u8], cache: &mut Cache, v_out: &mut [u8]) -> Result<(), String> {
+    inflate_to_with(v_in, InflateOptions::default(), cache, v_out)
+}
+
+pub fn inflate_to_with(
+    v_in: &[u8],
+    options: InflateOptions,
+    cache: &mut Cache,
+    v_out: &mut [u8],
+) -> Result<(), String> {
+    inflate_into(v_in, options, cache, v_out, |_| ())?;
+    Ok(())
+}
+
+pub(crate) fn inflate_into<O: Output + ?Sized>(
+    v_in: &[u8],
+    options: InflateOptions,
+    cache: &mut Cache,
+    out: &mut O,
+    update: impl FnMut(&[u8]),
+) -> Result<(usize, usize), String> {
     // Variable Initialization.
+    let strict = !options.permissive;
     let buf = cache.inner_mut();
-    let mut reader = Reader::new(v_in);
-    let mut bfinal = 0;
-    let mut i = 0;
+    let mut reader = BitReader::new(v_in);
+    let mut inflater = Inflater::new(out, 0, update);
 
     // Algorithms.
-    while bfinal == 0 {
-        bfinal = reader.read_bits(1);
-        let btype = reader.read_bits(2);
-        reader.check_last_error()?;
-        match btype {
-            0b11 => return Err(ERROR_RESERVED.into()),
-            0b00 => {
-                reader.reset();
-                let len = reader.read_u16();
-                let nlen = reader.read_u16();
-                if !len != nlen {
-                    return Err(ERROR_COMPLEMENT.into());
-                }
-                let len = len as usize;
-                reader.fill_to(v_out, i, len);
-                i += len;
+    read_blocks(&mut reader, buf, strict, &mut inflater)?;
+    reader.align();
+    Ok((inflater.len(), reader.position() / 8))
+}
+
+// Inflates keeping only the last 32 KB, and gives back the decompressed length and the bytes consumed.
+pub(crate) fn inflate_window(
+    v_in: &[u8],
+    buf: &mut [i32; 0x10000],
+    strict: bool,
+    mut update: impl FnMut(&[u8]),
+) -> Result<(usize, usize), String> {
+    // Variable Initialization.
+    let mut reader = BitReader::new(v_in);
+    let mut window = Window::default();
+    let mut len = 0;
+    let mut inflater = Inflater::new(&mut window, 0, |x: &[u8]| {
+        len += x.len();
+        update(x);
+    });
+
+    // Algorithms.
+    read_blocks(&mut reader, buf, strict, &mut inflater)?;
+    reader.align();
+    Ok((len, reader.position() / 8))
+}
+
+// Decodes without bounds checks while eight bytes of input and room for a whole match remain, and gives back the
+// index reached and whether the block ended. Anything unusual is left to the careful loop, which reports errors.
+#[inline(always)]
+fn inflate_fast(
+    decoder: &HuffmanDecoder,
+    reader: &mut BitReader,
+    v_out: &mut [u8],
+    mut i: usize,
+) -> (usize, bool) {
+    while i + FAST_MARGIN <= v_out.len() && reader.refill_fast() {
+        // At least 56 bits are loaded, and a match uses at most 48 of them.
+        let bits = reader.bits();
+        let entry = decoder.literal.entry(bits);
+        let mut used = (entry & ENTRY_WIDTH) as u8;
+        if entry & ENTRY_KIND == ENTRY_LITERALS {
+            v_out[i] = (entry >> 8) as u8;
+            if entry & ENTRY_PAIR > 0 {
+                v_out[i + 1] = (entry >> 16) as u8;
+                i += 1;
             }
-            btype => {
-                let decoder = HuffmanDecoder::new(BlockType::from(btype), &mut reader, buf)?;
-                reader.check_last_error()?;
-                loop {
-                    let x = decoder.decode(&mut reader);
-                    match x {
-                        Code::EndOfBlock => break,
-                        Code::Literal(a) => {
-                            if i >= v_out.len() {
-                                return Err(ERROR_BUFFER.into());
-                            }
-                            v_out[i] = a;
-                            i += 1;
-                        }
-                        Code::Pointer {
-                            distance: d,
-                            length: l,
-                        } => {
-                            let l = l as usize + 3;
-                            extend_to(v_out, i, d as usize, l)?;
-                            i += l;
-                        }
-                    }
-                }
+            i += 1;
+            reader.consume(used);
+            continue;
+        }
+        if entry & ENTRY_KIND != ENTRY_SYMBOL {
+            break;
+        }
+        let symbol = (entry >> 16) as usize;
+        match symbol {
+            0..=255 => {
+                v_out[i] = symbol as u8;
+                i += 1;
+                reader.consume(used);
+                continue;
+            }
+            256 => {
+                reader.consume(used);
+                return (i, true);
             }
+            257..=285 => (),
+            _ => break,
         }
+        let (base, width) = LENGTH_TABLE[symbol - 257];
+        let l = base as usize + 3 + ((bits >> used) & ((1 << width) - 1)) as usize;
+        used += width;
+
+        let dentry = decoder.distance.entry(bits >> used);
+        let (base, width) = match DISTANCE_TABLE.get((dentry >> 16) as usize) {
+            Some(x) if dentry & ENTRY_KIND == ENTRY_SYMBOL => *x,
+            _ => break,
+        };
+        used += (dentry & ENTRY_WIDTH) as u8;
+        let d = base as usize + ((bits >> used) & ((1 << width) - 1)) as usize;
+        used += width;
+        if d > i {
+            break;
+        }
+        reader.consume(used);
+        copy_match(v_out, i, d, l);
+        i += l;
     }
-    reader.check_last_error()
+    (i, false)
+}
+
+pub fn inflate_tokens(v_in: &[u8], cache: &mut Cache) -> Result<Vec<Code>, String> {
+    inflate_tokens_with(v_in, InflateOptions::default(), cache)
+}
+
+pub fn inflate_tokens_with(
+    v_in: &[u8],
+    options: InflateOptions,
+    cache: &mut Cache,
+) -> Result<Vec<Code>, String> {
+    Ok(inflate_tokens_len(v_in, options, cache)?.0)
+}
+
+pub(crate) fn inflate_tokens_len(
+    v_in: &[u8],
+    options: InflateOptions,
+    cache: &mut Cache,
+) -> Result<(Vec<Code>, usize), String> {
+    // Variable Initialization.
+    let strict = !options.permissive;
+    let buf = cache.inner_mut();
+    let mut reader = BitReader::new(v_in);
+    let mut tokens = Tokens {
+        v_out: Vec::new(),
+        pos: 0,
+    };
+
+    // Algorithms.
+    read_blocks(&mut reader, buf, strict, &mut tokens)?;
+    reader.align();
+    Ok((tokens.v_out, reader.position() / 8))
 }
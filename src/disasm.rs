@@ -0,0 +1,299 @@
+//! # Disassembler
+//!
+//! Walk a deflate, zlib or gzip stream block by block, infgen-style.
+//! Each block reports its bit offset, type, BFINAL, code lengths of dynamic
+//! blocks, compressed and uncompressed sizes and optionally its tokens.
+//!
+//! Decoding stops at the first error: the blocks read so far are kept, the
+//! failing block last, and the error is reported alongside them.
+//!
+//! ## Examples
+//!
+//! ### Structured data.
+//! ```
+//! use devker::prelude::{disassemble, zlib_encode, BlockType, Cache, Format, InflateOptions};
+//!
+//! let mut cache = Cache::new();
+//! let options = InflateOptions::default();
+//! let v_in = "Hello world ! ".repeat(4).into_bytes();
+//! let encoded = zlib_encode(&v_in, BlockType::Dynamic, &mut cache);
+//!
+//! let disassembly = disassemble(&encoded, Format::Zlib, true, options, &mut cache).unwrap();
+//! assert_eq!(disassembly.error, None);
+//! assert_eq!(disassembly.blocks.len(), 1);
+//! let block = &disassembly.blocks[0];
+//! assert_eq!(block.offset, 16);
+//! assert!(block.bfinal);
+//! assert_eq!(block.btype, BlockType::Dynamic);
+//! assert_eq!(block.uncompressed_size, v_in.len());
+//! assert_eq!(disassembly.trailer.len(), 4);
+//! ```
+//!
+//! ### Text.
+//! ```
+//! use devker::prelude::{deflate, disassemble, BlockType, Cache, Format, InflateOptions};
+//!
+//! let mut cache = Cache::new();
+//! let options = InflateOptions::default();
+//! let encoded = deflate(b"abcabcabc", BlockType::Fixed, &mut cache);
+//!
+//! let disassembly = disassemble(&encoded, Format::Deflate, true, options, &mut cache).unwrap();
+//! let text = disassembly.to_string();
+//! assert!(text.contains("last\nfixed\nliteral 'abc\nmatch 4 3\nliteral 'bc\nend\n"));
+//! ```
+
+// Imports.
+use crate::bits::BitReader;
+use crate::deflate::{decode_codes, read_block, HuffmanDecoder, Sink, Widthes};
+use crate::prelude::{BlockType, Cache, Code, Format, InflateOptions};
+use crate::{gzip, zlib};
+use std::fmt;
+// Constants.
+const MAX_LITERALS: usize = 60;
+// Structures.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub offset: usize,
+    pub bfinal: bool,
+    pub btype: BlockType,
+    pub wcount: usize,
+    pub wwidthes: [u8; 19],
+    pub lwidthes: Vec<u8>,
+    pub dwidthes: Vec<u8>,
+    pub tokens: Vec<Code>,
+    pub header_size: usize,
+    pub compressed_size: usize,
+    pub uncompressed_size: usize,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Disassembly {
+    pub format: Format,
+    pub header: Vec<u8>,
+    pub blocks: Vec<Block>,
+    pub trailer: Vec<u8>,
+    pub error: Option<String>,
+}
+// Fills a block from its header and codes, `pos` being where it starts in the output.
+struct Recorder<'a> {
+    block: &'a mut Block,
+    tokens: bool,
+    start: usize,
+    header_size: usize,
+    pos: usize,
+}
+// Implementations.
+impl Block {
+    fn new(offset: usize) -> Self {
+        Self {
+            offset,
+            bfinal: false,
+            btype: BlockType::Raw,
+            wcount: 0,
+            wwidthes: [0; 19],
+            lwidthes: Vec::new(),
+            dwidthes: Vec::new(),
+            tokens: Vec::new(),
+            header_size: 0,
+            compressed_size: 0,
+            uncompressed_size: 0,
+        }
+    }
+}
+impl<'a> Sink for Recorder<'a> {
+    fn block(&mut self, bfinal: bool, btype: Option<BlockType>) {
+        self.block.bfinal = bfinal;
+        if let Some(btype) = btype {
+            self.block.btype = btype;
+        }
+    }
+    fn widthes(&mut self, widthes: &Widthes) {
+        let (wcount, wwidthes, lwidthes, dwidthes) = widthes.clone();
+        self.block.wcount = wcount;
+        self.block.wwidthes = wwidthes;
+        self.block.lwidthes = lwidthes;
+        self.block.dwidthes = dwidthes;
+    }
+    fn header(&mut self, position: usize) {
+        self.header_size = position - self.start;
+    }
+    fn stored(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.block.header_size = self.header_size;
+        if self.tokens {
+            self.block
+                .tokens
+                .extend(bytes.iter().map(|x| Code::Literal(*x)));
+        }
+        self.block.uncompressed_size = bytes.len();
+        Ok(())
+    }
+    fn huffman(&mut self, decoder: &HuffmanDecoder, reader: &mut BitReader) -> Result<(), String> {
+        let (block, tokens) = (&mut *self.block, self.tokens);
+        block.header_size = self.header_size;
+        decode_codes(decoder, reader, self.pos, |code| {
+            block.uncompressed_size += match code {
+                Code::Pointer { length, .. } => length as usize + 3,
+                _ => 1,
+            };
+            if tokens {
+                block.tokens.push(code);
+            }
+        })?;
+        Ok(())
+    }
+}
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.format {
+            Format::Deflate => writeln!(f, "deflate")?,
+            Format::Zlib => writeln!(f, "zlib")?,
+            Format::Gzip => writeln!(f, "gzip")?,
+        }
+        if !self.header.is_empty() {
+            writeln!(f, "header {}", hex(&self.header))?;
+        }
+        for (i, block) in self.blocks.iter().enumerate() {
+            writeln!(
+                f,
+                "! block {} at bit {}: {} header bits, {} bits, {} bytes",
+                i, block.offset, block.header_size, block.compressed_size, block.uncompressed_size
+            )?;
+            let complete = self.error.is_none() || i + 1 < self.blocks.len();
+            write_block(f, block, complete)?;
+        }
+        if !self.trailer.is_empty() {
+            writeln!(f, "trailer {}", hex(&self.trailer))?;
+        }
+        if let Some(error) = &self.error {
+            writeln!(f, "! error {}", error)?;
+        }
+        Ok(())
+    }
+}
+// Functions.
+fn hex(bytes: &[u8]) -> String {
+    let hex = bytes.iter().map(|x| format!("{:02x}", x));
+    hex.collect::<Vec<_>>().join(" ")
+}
+fn write_literals(f: &mut fmt::Formatter, keyword: &str, literals: &[u8]) -> fmt::Result {
+    let mut text = String::new();
+    for x in literals {
+        if (0x20..0x7F).contains(x) && text.len() < MAX_LITERALS {
+            text.push(*x as char);
+            continue;
+        }
+        if !text.is_empty() {
+            writeln!(f, "{} '{}", keyword, text)?;
+            text.clear();
+        }
+        if (0x20..0x7F).contains(x) {
+            text.push(*x as char);
+        } else {
+            writeln!(f, "{} {}", keyword, x)?;
+        }
+    }
+    if !text.is_empty() {
+        writeln!(f, "{} '{}", keyword, text)?;
+    }
+    Ok(())
+}
+fn write_block(f: &mut fmt::Formatter, block: &Block, complete: bool) -> fmt::Result {
+    if block.bfinal {
+        writeln!(f, "last")?;
+    }
+    match block.btype {
+        BlockType::Raw => writeln!(f, "stored")?,
+        BlockType::Fixed => writeln!(f, "fixed")?,
+        BlockType::Dynamic => {
+            writeln!(f, "dynamic")?;
+            let (lcount, dcount) = (block.lwidthes.len(), block.dwidthes.len());
+            writeln!(f, "count {} {} {}", lcount, dcount, block.wcount)?;
+            let widthes = [
+                ("code", &block.wwidthes[..]),
+                ("litlen", &block.lwidthes),
+                ("dist", &block.dwidthes),
+            ];
+            for (keyword, widthes) in widthes.iter() {
+                for (symbol, width) in widthes.iter().enumerate().filter(|(_, x)| **x > 0) {
+                    writeln!(f, "{} {} {}", keyword, symbol, width)?;
+                }
+            }
+        }
+    }
+    let keyword = match block.btype {
+        BlockType::Raw => "data",
+        _ => "literal",
+    };
+    let mut literals = Vec::new();
+    for code in &block.tokens {
+        match *code {
+            Code::Literal(x) => literals.push(x),
+            Code::Pointer { length, distance } => {
+                write_literals(f, keyword, &literals)?;
+                literals.clear();
+                writeln!(f, "match {} {}", length as usize + 3, distance)?;
+            }
+            Code::EndOfBlock => (),
+        }
+    }
+    write_literals(f, keyword, &literals)?;
+    if complete {
+        writeln!(f, "end")?;
+    }
+    Ok(())
+}
+// Main functions.
+pub fn disassemble(
+    v_in: &[u8],
+    format: Format,
+    tokens: bool,
+    options: InflateOptions,
+    cache: &mut Cache,
+) -> Result<Disassembly, String> {
+    // Conditions.
+    let header_len = match format {
+        Format::Deflate => 0,
+        Format::Zlib => zlib::read_header(v_in)?,
+        Format::Gzip => gzip::read_header(v_in)?,
+    };
+    // Variable initialization.
+    let strict = !options.permissive;
+    let buf = cache.inner_mut();
+    let mut reader = BitReader::new(&v_in[header_len..]);
+    let mut disassembly = Disassembly {
+        format,
+        header: v_in[..header_len].to_vec(),
+        blocks: Vec::new(),
+        trailer: Vec::new(),
+        error: None,
+    };
+    let mut pos = 0;
+
+    // Algorithm.
+    loop {
+        let start = reader.position();
+        let mut block = Block::new(header_len * 8 + start);
+        let mut recorder = Recorder {
+            block: &mut block,
+            tokens,
+            start,
+            header_size: 0,
+            pos,
+        };
+        let result = read_block(&mut reader, buf, strict, &mut recorder);
+        pos += block.uncompressed_size;
+        block.compressed_size = reader.position() - start;
+        let bfinal = block.bfinal;
+        disassembly.blocks.push(block);
+        if let Err(error) = result {
+            disassembly.error = Some(error);
+            return Ok(disassembly);
+        }
+        if bfinal {
+            break;
+        }
+    }
+    reader.align();
+    let end = header_len + reader.position() / 8;
+    disassembly.trailer = v_in[end..].to_vec();
+    Ok(disassembly)
+}
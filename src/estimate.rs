@@ -0,0 +1,125 @@
+//! # Size estimation
+//!
+//! Compressed size of deflate's output, without writing the bit stream.
+//! The match finder, the strategy, the blocks and the code widthes are the
+//! ones of [`deflate`] with the same options, only the cost of each code is
+//! summed up: without sampling, the estimate is exact.
+//!
+//! With sampling, one chunk of 16 KB every `sampling` chunks is compressed,
+//! and the size of the payload is scaled up to the whole input.
+//!
+//! ## Examples
+//!
+//! ### Easy to use.
+//! ```
+//! use devker::prelude::{deflate, estimate_deflate_size, BlockType, Cache};
+//!
+//! let mut cache = Cache::new();
+//! let v_in = "Hello world, this is a wonderful world !".repeat(100).into_bytes();
+//!
+//! let size = estimate_deflate_size(&v_in, BlockType::Dynamic, &mut cache);
+//! assert_eq!(size, deflate(&v_in, BlockType::Dynamic, &mut cache).len());
+//! ```
+//!
+//! ### Sampling.
+//! ```
+//! use devker::prelude::{estimate_deflate_size, BlockType, Cache, EstimateOptions, Strategy};
+//!
+//! let mut cache = Cache::new();
+//! let v_in = vec![0; 1 << 20];
+//! let options = EstimateOptions {
+//!     btype: BlockType::Fixed,
+//!     strategy: Strategy::Greedy,
+//!     sampling: 16,
+//! };
+//!
+//! let size = estimate_deflate_size(&v_in, options, &mut cache);
+//! assert!(size < v_in.len() / 100);
+//! ```
+//!
+//! ### Strategies.
+//! ```
+//! use devker::prelude::{deflate, estimate_deflate_size, BlockType, Cache, DeflateOptions, Strategy};
+//!
+//! let mut cache = Cache::new();
+//! let v_in = "Hello world, this is a wonderful world !".repeat(100).into_bytes();
+//! let options = DeflateOptions {
+//!     btype: BlockType::Dynamic,
+//!     strategy: Strategy::HuffmanOnly,
+//! };
+//!
+//! let size = estimate_deflate_size(&v_in, options, &mut cache);
+//! assert_eq!(size, deflate(&v_in, options, &mut cache).len());
+//! ```
+//!
+//! [`deflate`]: ../deflate/fn.deflate.html
+
+// Imports.
+use crate::huffman::huffman_size;
+use crate::lzss::Lzss;
+use crate::prelude::{BlockType, Cache, DeflateOptions, Strategy};
+// Constants.
+const BLOCK_LENGTH: usize = 0x10000;
+const SAMPLE_LEN: usize = 0x4000;
+const MAX_STORED_LENGTH: usize = 0xFFFF;
+// Structures.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EstimateOptions {
+    pub btype: BlockType,
+    pub strategy: Strategy,
+    pub sampling: usize,
+}
+// Implementations.
+impl From<BlockType> for EstimateOptions {
+    fn from(btype: BlockType) -> Self {
+        Self::from(DeflateOptions::from(btype))
+    }
+}
+impl From<DeflateOptions> for EstimateOptions {
+    fn from(options: DeflateOptions) -> Self {
+        Self {
+            btype: options.btype,
+            strategy: options.strategy,
+            sampling: 1,
+        }
+    }
+}
+// Main functions.
+pub fn estimate_deflate_size(
+    v_in: &[u8],
+    options: impl Into<EstimateOptions>,
+    cache: &mut Cache,
+) -> usize {
+    // Variable initialization.
+    let options = options.into();
+
+    // Algorithm.
+    if options.btype == BlockType::Raw {
+        let blocks = std::cmp::max(1, v_in.len().div_ceil(MAX_STORED_LENGTH));
+        return v_in.len() + 5 * blocks;
+    }
+    if options.sampling <= 1 || v_in.len() <= options.sampling.saturating_mul(SAMPLE_LEN) {
+        let mut lzss = Lzss::new(cache, options.strategy);
+        let mut size = 0;
+        let mut chunks = v_in.chunks(BLOCK_LENGTH).peekable();
+        loop {
+            let chunk = chunks.next().unwrap_or_default();
+            let bfinal = chunks.peek().is_none();
+            let tokens = lzss.encode(chunk, bfinal);
+            let (header_size, payload_size) = huffman_size(tokens, options.btype);
+            size += header_size + payload_size;
+            if bfinal {
+                return size.div_ceil(8);
+            }
+        }
+    }
+    let mut encoded = Vec::new();
+    let mut sampled = 0;
+    for chunk in v_in.chunks(SAMPLE_LEN).step_by(options.sampling) {
+        encoded.extend_from_slice(Lzss::new(cache, options.strategy).encode(chunk, true));
+        sampled += chunk.len();
+    }
+    let (header_size, payload_size) = huffman_size(&encoded, options.btype);
+    let payload_size = payload_size as u64 * v_in.len() as u64 / sampled as u64;
+    (header_size + payload_size as usize).div_ceil(8)
+}
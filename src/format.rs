@@ -0,0 +1,14 @@
+//! # Format
+//!
+//! Help to determine which wrapper is around deflate's data.
+//! - Deflate: no wrapper.
+//! - Zlib: 2 bytes header, Adler-32 trailer.
+//! - Gzip: 10 bytes header or more, CRC-32 and size trailer.
+
+// Structures.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Deflate,
+    Zlib,
+    Gzip,
+}
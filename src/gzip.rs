@@ -0,0 +1,176 @@
+//! # Gzip encode/decode
+//!
+//! A gzip file is a list of members, each one a deflate stream with its own
+//! header and trailer. Decoding reads every member and returns their data as
+//! one stream.
+//!
+//! ## Examples
+//!
+//! ### Easy to use.
+//! ```
+//! use devker::prelude::{gzip_decode, gzip_encode, BlockType, Cache};
+//!
+//! let mut cache = Cache::new();
+//! let v = String::from("Hello world, this is a wonderful world !");
+//! let v_in = v.into_bytes();
+//!
+//! // Encode.
+//! let encoded = gzip_encode(&v_in, BlockType::Fixed, &mut cache);
+//! // Decode.
+//! let decoded = gzip_decode(&encoded, &mut cache).unwrap();
+//! assert_eq!(v_in, decoded);
+//! ```
+//!
+//! ### Multiple members.
+//! ```
+//! use devker::prelude::{gzip_decode, gzip_encode, BlockType, Cache};
+//!
+//! let mut cache = Cache::new();
+//!
+//! let mut encoded = gzip_encode(b"Hello world, ", BlockType::Fixed, &mut cache);
+//! encoded.extend(gzip_encode(b"this is a wonderful world !", BlockType::Dynamic, &mut cache));
+//! let decoded = gzip_decode(&encoded, &mut cache).unwrap();
+//! assert_eq!(decoded, b"Hello world, this is a wonderful world !");
+//! ```
+
+// Imports.
+use crate::crc32::Crc32;
+use crate::deflate::{deflate_into, deflate_read, inflate_len, write_all};
+use crate::prelude::{Cache, DeflateOptions, InflateOptions};
+use std::io::{Read, Write};
+// Constants.
+const ERROR_CRC32: &str = "Gzip checksum error";
+const ERROR_DEFLATE: &str = "Gzip only supports deflate compression algorithm";
+const ERROR_ID: &str = "Invalid gzip magic number";
+const ERROR_LENGTH: &str = "Gzip's header is missing";
+const ERROR_SIZE: &str = "Gzip size error";
+pub(crate) const ERROR_TRAILER: &str = "Gzip's trailer is missing";
+pub(crate) const ID: [u8; 2] = [0x1F, 0x8B];
+const HEADER_LEN: usize = 10;
+pub(crate) const TRAILER_LEN: usize = 8;
+pub(crate) const METHOD_DEFLATE: u8 = 8;
+const FHCRC: u8 = 0b0000_0010;
+const FEXTRA: u8 = 0b0000_0100;
+const FNAME: u8 = 0b0000_1000;
+const FCOMMENT: u8 = 0b0001_0000;
+const OS_UNKNOWN: u8 = 0xFF;
+// Functions.
+fn skip_string(v_in: &[u8], pos: usize) -> Result<usize, String> {
+    match v_in.get(pos..).and_then(|x| x.iter().position(|x| *x == 0)) {
+        Some(len) => Ok(pos + len + 1),
+        None => Err(ERROR_LENGTH.into()),
+    }
+}
+pub(crate) fn read_header(v_in: &[u8]) -> Result<usize, String> {
+    if v_in.len() < HEADER_LEN {
+        return Err(ERROR_LENGTH.into());
+    }
+    if v_in[..2] != ID {
+        return Err(ERROR_ID.into());
+    }
+    if v_in[2] != METHOD_DEFLATE {
+        return Err(ERROR_DEFLATE.into());
+    }
+    let flg = v_in[3];
+    let mut pos = HEADER_LEN;
+    if flg & FEXTRA > 0 {
+        let xlen = match v_in.get(pos..pos + 2) {
+            Some(x) => u16::from_le_bytes([x[0], x[1]]) as usize,
+            None => return Err(ERROR_LENGTH.into()),
+        };
+        pos += 2 + xlen;
+    }
+    if flg & FNAME > 0 {
+        pos = skip_string(v_in, pos)?;
+    }
+    if flg & FCOMMENT > 0 {
+        pos = skip_string(v_in, pos)?;
+    }
+    if flg & FHCRC > 0 {
+        pos += 2;
+    }
+    if pos > v_in.len() {
+        return Err(ERROR_LENGTH.into());
+    }
+    Ok(pos)
+}
+pub(crate) fn check_checksum(trailer: &[u8], checksum: [u8; 4], len: usize) -> Result<(), String> {
+    if checksum[..] != trailer[..4] {
+        return Err(ERROR_CRC32.into());
+    }
+    if (len as u32).to_le_bytes()[..] != trailer[4..] {
+        return Err(ERROR_SIZE.into());
+    }
+    Ok(())
+}
+// Main functions.
+pub fn gzip_encode(v_in: &[u8], options: impl Into<DeflateOptions>, cache: &mut Cache) -> Vec<u8> {
+    // Variable initialization.
+    let mut crc32 = Crc32::new();
+    let mut v_out = Vec::new();
+
+    // Algorithm.
+    v_out.extend(&ID);
+    v_out.extend(&[METHOD_DEFLATE, 0, 0, 0, 0, 0, 0, OS_UNKNOWN]);
+    deflate_into(v_in, options.into(), cache, &mut v_out, None, |x| {
+        crc32.update(x)
+    });
+    v_out.extend(&crc32.checksum());
+    v_out.extend(&(v_in.len() as u32).to_le_bytes());
+    v_out
+}
+
+pub fn gzip_encode_stream(
+    mut reader: impl Read,
+    mut writer: impl Write,
+    options: impl Into<DeflateOptions>,
+    cache: &mut Cache,
+) -> Result<usize, String> {
+    // Variable initialization.
+    let mut crc32 = Crc32::new();
+
+    // Algorithm.
+    write_all(&mut writer, &ID)?;
+    write_all(&mut writer, &[METHOD_DEFLATE, 0, 0, 0, 0, 0, 0, OS_UNKNOWN])?;
+    let len = deflate_read(&mut reader, &mut writer, options.into(), cache, |x| {
+        crc32.update(x)
+    })?;
+    write_all(&mut writer, &crc32.checksum())?;
+    write_all(&mut writer, &(len as u32).to_le_bytes())?;
+    Ok(len)
+}
+
+pub fn gzip_decode(v_in: &[u8], cache: &mut Cache) -> Result<Vec<u8>, String> {
+    gzip_decode_with(v_in, InflateOptions::default(), cache)
+}
+
+pub fn gzip_decode_with(
+    v_in: &[u8],
+    options: InflateOptions,
+    cache: &mut Cache,
+) -> Result<Vec<u8>, String> {
+    // Variable initialization.
+    let mut v_out = Vec::new();
+    let mut pos = 0;
+
+    // Algorithm.
+    loop {
+        let header_len = read_header(&v_in[pos..])?;
+        let mut crc32 = Crc32::new();
+        let (mut data, len) = inflate_len(&v_in[pos + header_len..], options, cache, |x| {
+            crc32.update(x)
+        })?;
+        pos += header_len + len;
+        let trailer = match v_in.get(pos..pos + TRAILER_LEN) {
+            Some(trailer) => trailer,
+            None => return Err(ERROR_TRAILER.into()),
+        };
+        check_checksum(trailer, crc32.checksum(), data.len())?;
+        v_out.append(&mut data);
+        pos += TRAILER_LEN;
+        if pos == v_in.len() {
+            break;
+        }
+    }
+    Ok(v_out)
+}
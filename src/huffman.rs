@@ -1,115 +1,557 @@
 //! # Huffman's Coding
+//!
+//! Canonical Huffman codes for any alphabet size.
+//! - [`build_widthes`]: length-limited code widthes from frequencies (package-merge).
+//! - [`canonical_codes`]: canonical codes from code widthes.
+//! - [`Encoder`]: writes symbols into a [`BitWriter`].
+//! - [`Decoder`]: lookup table indexed by the next `max_width` bits,
+//!   reads symbols from a [`BitReader`].
+//!
+//! ## Examples
+//!
+//! ### Easy to use.
+//! ```
+//! use devker::bits::{BitReader, BitWriter};
+//! use devker::huffman::{build_widthes, Decoder, Encoder};
+//!
+//! let frequencies = [10, 1, 1, 5, 0, 20];
+//! let widthes = build_widthes(&frequencies, 3).unwrap();
+//! assert_eq!(widthes, [3, 3, 3, 3, 0, 1]);
+//!
+//! // Encode.
+//! let encoder = Encoder::new(&widthes).unwrap();
+//! let mut writer = BitWriter::new();
+//! for symbol in [5, 0, 3, 1, 2] {
+//!     encoder.encode(&mut writer, symbol);
+//! }
+//! let encoded = writer.finish();
+//!
+//! // Decode.
+//! let decoder = Decoder::new(&widthes).unwrap();
+//! let mut reader = BitReader::new(&encoded);
+//! let mut decoded = Vec::new();
+//! for _ in 0..5 {
+//!     decoded.push(decoder.decode(&mut reader).unwrap());
+//! }
+//! assert_eq!(decoded, [5, 0, 3, 1, 2]);
+//!
+//! // Widthes over 16 bits or over-subscribed are rejected.
+//! assert!(Encoder::new(&[17, 1]).is_err());
+//! assert!(Encoder::new(&[1, 1, 1]).is_err());
+//! ```
+//!
+//! ### Longest codes.
+//! ```
+//! use devker::bits::{BitReader, BitWriter};
+//! use devker::huffman::{build_widthes, Decoder, Encoder};
+//!
+//! // Fibonacci frequencies give the deepest tree, limited here to 16 bits.
+//! let mut frequencies = vec![1, 1];
+//! while frequencies.len() < 20 {
+//!     frequencies.push(frequencies[frequencies.len() - 1] + frequencies[frequencies.len() - 2]);
+//! }
+//! let widthes = build_widthes(&frequencies, 16).unwrap();
+//! assert_eq!(widthes.iter().max(), Some(&16));
+//!
+//! let encoder = Encoder::new(&widthes).unwrap();
+//! let mut writer = BitWriter::new();
+//! for symbol in 0..20 {
+//!     encoder.encode(&mut writer, symbol);
+//! }
+//! let encoded = writer.finish();
+//!
+//! let decoder = Decoder::new(&widthes).unwrap();
+//! let mut reader = BitReader::new(&encoded);
+//! for symbol in 0..20 {
+//!     assert_eq!(decoder.decode(&mut reader), Ok(symbol));
+//! }
+//! ```
+//!
+//! [`build_widthes`]: fn.build_widthes.html
+//! [`canonical_codes`]: fn.canonical_codes.html
+//! [`Encoder`]: struct.Encoder.html
+//! [`Decoder`]: struct.Decoder.html
+//! [`BitReader`]: ../bits/struct.BitReader.html
+//! [`BitWriter`]: ../bits/struct.BitWriter.html
 
 // Imports.
-use crate::bits::Bits;
-use crate::code::Code;
+use crate::bits::{BitReader, BitWriter, Bits};
+use crate::code::{Token, END_OF_BLOCK, WIDTH_CODE_ORDER};
 use crate::prelude::BlockType;
+use crate::stats::BlockStats;
+// Constants.
+const ERROR_CODE: &str = "Invalid code decoded.";
+const ERROR_MAX_WIDTH: &str = "Maximum code width must be between 1 and 16.";
+const ERROR_OVERSUBSCRIBED: &str = "Over-subscribed code lengths.";
+const ERROR_SYMBOLS: &str = "Too many symbols for the maximum code width.";
+const MAX_WIDTH: u8 = 16;
+const MAX_LWIDTH: u8 = 15;
+const MAX_WWIDTH: u8 = 7;
+const SPLIT_LEN: usize = 0x1000;
 // Structures.
-#[derive(Debug)]
-struct Writer {
-    v_out: Vec<u8>,
-    buf: u32,
-    width: u8,
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    codes: Vec<Bits>,
 }
-#[derive(Debug)]
-struct HuffmanEncoder<'a> {
-    literal: &'a mut [i32],
-    distance: &'a mut [i32],
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    table: Vec<i32>,
+    max_width: u8,
+}
+#[derive(Debug, Clone, Copy)]
+enum Node {
+    Leaf(usize),
+    Package(usize, usize),
+}
+struct HuffmanEncoder {
+    literal: Encoder,
+    distance: Encoder,
 }
 // Implementations.
-impl Writer {
-    fn new() -> Self {
+impl Encoder {
+    pub fn new(widthes: &[u8]) -> Result<Self, String> {
+        check_widthes(widthes)?;
+        Ok(Self::unchecked(widthes))
+    }
+    // Any widthes up to 16, over-subscribed ones are written as given.
+    pub(crate) fn unchecked(widthes: &[u8]) -> Self {
+        let codes = canonical_codes(widthes).iter().map(Bits::reverse).collect();
+        Self { codes }
+    }
+    pub fn code(&self, symbol: usize) -> Bits {
+        self.codes[symbol]
+    }
+    pub fn encode(&self, writer: &mut BitWriter, symbol: usize) {
+        writer.write_bits(self.codes[symbol]);
+    }
+}
+impl Decoder {
+    pub fn new(widthes: &[u8]) -> Result<Self, String> {
+        let max_width = check_widthes(widthes)?;
+        let mut table = vec![0; 1 << max_width];
+        restore_canonical_huffman_codes(&mut table, widthes, max_width);
+        Ok(Self { table, max_width })
+    }
+    pub fn max_width(&self) -> u8 {
+        self.max_width
+    }
+    pub fn lookup(&self, code: u16) -> Option<Bits> {
+        let mask = ((1u32 << self.max_width) - 1) as u16;
+        let bits = Bits::from(self.table[(code & mask) as usize]);
+        if bits.width == 0 {
+            return None;
+        }
+        Some(bits)
+    }
+    pub fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        let code = reader.peek_bits(self.max_width);
+        let bits = match self.lookup(code as u16) {
+            Some(bits) => bits,
+            None => return Err(ERROR_CODE.into()),
+        };
+        reader.skip_bits(bits.width)?;
+        Ok(bits.data)
+    }
+}
+impl HuffmanEncoder {
+    fn new(btype: BlockType, v_in: &[Token], writer: &mut BitWriter) -> Self {
+        let (lwidthes, dwidthes) = block_widthes(btype, v_in);
+        if btype == BlockType::Dynamic {
+            write_widthes(writer, &lwidthes, &dwidthes);
+        }
         Self {
-            v_out: Vec::new(),
-            buf: 0,
-            width: 0,
+            literal: Encoder::unchecked(&lwidthes),
+            distance: Encoder::unchecked(&dwidthes),
         }
     }
-    fn write_bits(&mut self, bits: Bits) {
-        self.buf |= (bits.data as u32) << self.width;
-        self.width += bits.width;
-
-        if self.width >= 16 {
-            self.v_out.push(self.buf as u8);
-            self.v_out.push((self.buf >> 8) as u8);
-            self.width -= 16;
-            self.buf >>= 16;
-        }
-    }
-    fn finish(mut self) -> Vec<u8> {
-        if self.width > 8 {
-            self.v_out.push(self.buf as u8);
-            self.v_out.push((self.buf >> 8) as u8);
-        } else if self.width > 0 {
-            self.v_out.push(self.buf as u8);
-        }
-        self.buf >>= 16;
-        self.width = self.width.saturating_sub(16);
-        self.v_out
-    }
-}
-impl<'a> HuffmanEncoder<'a> {
-    fn new(btype: BlockType, _v_in: &[Code], buf: &'a mut [i32; 0x10000]) -> Self {
-        match btype {
-            BlockType::Fixed => {
-                let (literal, buf) = buf.split_at_mut(286);
-                let (distance, _) = buf.split_at_mut(30);
-
-                // Fixed Huffman Tree
-                for i in 0..144 {
-                    let (data, width) = (0b0_0011_0000 + i as u16, 8);
-                    literal[i] = Bits { data, width }.reverse().as_i32();
-                }
-                for (i, j) in (144..256).enumerate() {
-                    let (data, width) = (0b1_1001_0000 + i as u16, 9);
-                    literal[j] = Bits { data, width }.reverse().as_i32();
-                }
-                for (i, j) in (256..280).enumerate() {
-                    let (data, width) = (0b0_0000_0000 + i as u16, 7);
-                    literal[j] = Bits { data, width }.reverse().as_i32();
-                }
-                for (i, j) in (280..286).enumerate() {
-                    let (data, width) = (0b0_1100_0000 + i as u16, 8);
-                    literal[j] = Bits { data, width }.reverse().as_i32();
-                }
-                for i in 0..30 {
-                    let (width, data) = (5, i as u16);
-                    distance[i] = Bits { data, width }.reverse().as_i32();
-                }
+    fn encode(&self, writer: &mut BitWriter, token: Token) {
+        self.literal.encode(writer, token.symbol());
+        if token.is_pointer() {
+            writer.write_bits(token.length_extra());
+            self.distance.encode(writer, token.distance_symbol());
+            writer.write_bits(token.distance_extra());
+        }
+    }
+}
+// Functions.
+fn check_widthes(widthes: &[u8]) -> Result<u8, String> {
+    // At most 16 bits, and Kraft's sum of at most 1: codes fit in the tree.
+    let max_width = *widthes.iter().max().unwrap_or(&0);
+    if max_width > MAX_WIDTH {
+        return Err(ERROR_MAX_WIDTH.into());
+    }
+    let kraft = widthes
+        .iter()
+        .filter(|width| **width > 0)
+        .map(|width| 1u32 << (max_width - width))
+        .sum::<u32>();
+    if kraft > 1 << max_width {
+        return Err(ERROR_OVERSUBSCRIBED.into());
+    }
+    Ok(max_width)
+}
+fn force_two_codes(frequencies: &mut [usize]) {
+    // Some decoders reject a code with a single symbol.
+    let mut count = frequencies.iter().filter(|x| **x > 0).count();
+    for x in frequencies.iter_mut() {
+        if count >= 2 {
+            break;
+        }
+        if *x == 0 {
+            *x = 1;
+            count += 1;
+        }
+    }
+}
+fn frequencies(v_in: &[Token]) -> ([usize; 286], [usize; 30]) {
+    let mut lfrequencies = [0; 286];
+    let mut dfrequencies = [0; 30];
+    for token in v_in {
+        lfrequencies[token.symbol()] += 1;
+        if token.is_pointer() {
+            dfrequencies[token.distance_symbol()] += 1;
+        }
+    }
+    lfrequencies[END_OF_BLOCK as usize] += 1;
+    (lfrequencies, dfrequencies)
+}
+fn dynamic_widthes(lfrequencies: &[usize; 286], dfrequencies: &[usize; 30]) -> (Vec<u8>, Vec<u8>) {
+    let (mut lfrequencies, mut dfrequencies) = (*lfrequencies, *dfrequencies);
+    force_two_codes(&mut lfrequencies);
+    force_two_codes(&mut dfrequencies);
+    let lwidthes = build_widthes(&lfrequencies, MAX_LWIDTH).unwrap();
+    let dwidthes = build_widthes(&dfrequencies, MAX_LWIDTH).unwrap();
+    (lwidthes, dwidthes)
+}
+fn dynamic_cost(lfrequencies: &[usize; 286], dfrequencies: &[usize; 30]) -> usize {
+    // Extra bits don't depend on the block split, they are left out.
+    let (lwidthes, dwidthes) = dynamic_widthes(lfrequencies, dfrequencies);
+    let lcost = lfrequencies
+        .iter()
+        .zip(&lwidthes)
+        .map(|(f, w)| f * *w as usize);
+    let dcost = dfrequencies
+        .iter()
+        .zip(&dwidthes)
+        .map(|(f, w)| f * *w as usize);
+    3 + widthes_size(&lwidthes, &dwidthes) + lcost.sum::<usize>() + dcost.sum::<usize>()
+}
+fn block_widthes(btype: BlockType, v_in: &[Token]) -> (Vec<u8>, Vec<u8>) {
+    match btype {
+        BlockType::Fixed => {
+            let (lwidthes, dwidthes) = fixed_widthes();
+            (lwidthes.to_vec(), dwidthes.to_vec())
+        }
+        BlockType::Dynamic => {
+            let (lfrequencies, dfrequencies) = frequencies(v_in);
+            dynamic_widthes(&lfrequencies, &dfrequencies)
+        }
+        _ => unimplemented!(),
+    }
+}
+fn dynamic_header(lwidthes: &[u8], dwidthes: &[u8]) -> (usize, usize, Vec<(u8, Bits)>) {
+    let lcount = std::cmp::max(257, lwidthes.iter().rposition(|x| *x > 0).unwrap_or(0) + 1);
+    let dcount = std::cmp::max(1, dwidthes.iter().rposition(|x| *x > 0).unwrap_or(0) + 1);
+    let codes = width_codes(&[&lwidthes[..lcount], &dwidthes[..dcount]].concat());
+    (lcount, dcount, codes)
+}
+fn write_widthes(writer: &mut BitWriter, lwidthes: &[u8], dwidthes: &[u8]) {
+    let (lcount, dcount, codes) = dynamic_header(lwidthes, dwidthes);
+    let (width_code_widthes, wcount) = width_code_widthes(&codes);
+    write_width_codes(writer, &width_code_widthes, wcount, lcount, dcount, &codes);
+}
+fn widthes_size(lwidthes: &[u8], dwidthes: &[u8]) -> usize {
+    let (_, _, codes) = dynamic_header(lwidthes, dwidthes);
+    let (width_code_widthes, wcount) = width_code_widthes(&codes);
+    let codes_size = codes
+        .iter()
+        .map(|(code, extra)| (width_code_widthes[*code as usize] + extra.width) as usize);
+    5 + 5 + 4 + 3 * wcount + codes_size.sum::<usize>()
+}
+fn code_size(lwidthes: &[u8], dwidthes: &[u8], token: Token) -> usize {
+    let mut size = lwidthes[token.symbol()] as usize;
+    if token.is_pointer() {
+        let width = dwidthes[token.distance_symbol()] + token.length_extra().width;
+        size += (width + token.distance_extra().width) as usize;
+    }
+    size
+}
+pub(crate) fn width_codes(widthes: &[u8]) -> Vec<(u8, Bits)> {
+    // Run-length encoding.
+    let mut codes = Vec::new();
+    let mut i = 0;
+    while i < widthes.len() {
+        let width = widthes[i];
+        let mut run = widthes[i..].iter().take_while(|x| **x == width).count();
+        i += run;
+        if width == 0 {
+            while run >= 11 {
+                let count = std::cmp::min(run, 138);
+                codes.push((18, Bits::new(count as u16 - 11, 7)));
+                run -= count;
+            }
+            if run >= 3 {
+                codes.push((17, Bits::new(run as u16 - 3, 3)));
+                run = 0;
+            }
+        } else {
+            codes.push((width, Bits::new(0, 0)));
+            run -= 1;
+            while run >= 3 {
+                let count = std::cmp::min(run, 6);
+                codes.push((16, Bits::new(count as u16 - 3, 2)));
+                run -= count;
+            }
+        }
+        codes.extend(std::iter::repeat_n((width, Bits::new(0, 0)), run));
+    }
+    codes
+}
+pub(crate) fn width_code_widthes(codes: &[(u8, Bits)]) -> ([u8; 19], usize) {
+    let mut frequencies = [0; 19];
+    for (code, _) in codes {
+        frequencies[*code as usize] += 1;
+    }
+    force_two_codes(&mut frequencies);
+    let mut width_code_widthes = [0; 19];
+    width_code_widthes.copy_from_slice(&build_widthes(&frequencies, MAX_WWIDTH).unwrap());
+    let wcount = WIDTH_CODE_ORDER
+        .iter()
+        .rposition(|x| width_code_widthes[*x] > 0)
+        .map_or(4, |x| std::cmp::max(4, x + 1));
+    (width_code_widthes, wcount)
+}
+pub(crate) fn write_width_codes(
+    writer: &mut BitWriter,
+    width_code_widthes: &[u8; 19],
+    wcount: usize,
+    lcount: usize,
+    dcount: usize,
+    codes: &[(u8, Bits)],
+) {
+    let encoder = Encoder::unchecked(width_code_widthes);
+    writer.write_bits(Bits::new(lcount as u16 - 257, 5));
+    writer.write_bits(Bits::new(dcount as u16 - 1, 5));
+    writer.write_bits(Bits::new(wcount as u16 - 4, 4));
+    for i in WIDTH_CODE_ORDER.iter().take(wcount) {
+        writer.write_bits(Bits::new(width_code_widthes[*i] as u16, 3));
+    }
+    for (code, extra) in codes {
+        encoder.encode(writer, *code as usize);
+        writer.write_bits(*extra);
+    }
+}
+pub(crate) fn fixed_widthes() -> ([u8; 288], [u8; 30]) {
+    let mut lwidthes = [8; 288];
+    lwidthes[144..256].copy_from_slice(&[9; 112]);
+    lwidthes[256..280].copy_from_slice(&[7; 24]);
+    (lwidthes, [5; 30])
+}
+pub(crate) fn set_mapping(decoder: &mut [i32], code: u16, data: u16, width: u8, max_width: u8) {
+    let bits = Bits { data, width }.reverse();
+    for padding in 0..(1 << (max_width - width)) {
+        decoder[padding << width | bits.data as usize] = Bits { data: code, width }.as_i32();
+    }
+}
+pub(crate) fn restore_canonical_huffman_codes(decoder: &mut [i32], widthes: &[u8], max_width: u8) {
+    for (code, bits) in canonical_codes(widthes).into_iter().enumerate() {
+        if bits.width > 0 {
+            set_mapping(decoder, code as u16, bits.data, bits.width, max_width);
+        }
+    }
+}
+pub fn build_widthes(frequencies: &[usize], max_width: u8) -> Result<Vec<u8>, String> {
+    // Conditions.
+    if max_width == 0 || max_width > MAX_WIDTH {
+        return Err(ERROR_MAX_WIDTH.into());
+    }
+    // Variable initialization.
+    let mut widthes = vec![0; frequencies.len()];
+    let mut leaves = frequencies
+        .iter()
+        .enumerate()
+        .filter(|(_, frequency)| **frequency > 0)
+        .map(|(symbol, frequency)| (*frequency, symbol))
+        .collect::<Vec<_>>();
+    leaves.sort_unstable();
+    match leaves.len() {
+        0 => return Ok(widthes),
+        1 => {
+            widthes[leaves[0].1] = 1;
+            return Ok(widthes);
+        }
+        n if n > 1 << max_width => return Err(ERROR_SYMBOLS.into()),
+        _ => (),
+    }
+    let mut nodes = leaves
+        .iter()
+        .map(|(_, symbol)| Node::Leaf(*symbol))
+        .collect::<Vec<_>>();
+    let leaves = leaves
+        .iter()
+        .enumerate()
+        .map(|(i, (frequency, _))| (*frequency, i))
+        .collect::<Vec<_>>();
 
-                Self { literal, distance }
+    // Package-merge.
+    let mut items = leaves.clone();
+    for _ in 1..max_width {
+        let packages = items
+            .chunks_exact(2)
+            .map(|pair| {
+                nodes.push(Node::Package(pair[0].1, pair[1].1));
+                (pair[0].0 + pair[1].0, nodes.len() - 1)
+            })
+            .collect::<Vec<_>>();
+        items = Vec::with_capacity(leaves.len() + packages.len());
+        let (mut i, mut j) = (0, 0);
+        while i < leaves.len() || j < packages.len() {
+            if j == packages.len() || (i < leaves.len() && leaves[i].0 <= packages[j].0) {
+                items.push(leaves[i]);
+                i += 1;
+            } else {
+                items.push(packages[j]);
+                j += 1;
             }
-            _ => unimplemented!(),
         }
     }
-    fn encode(&self, writer: &mut Writer, code: Code) {
-        let lcode = self.literal[code.literal_code() as usize];
-        let bits = Bits::from(lcode);
-        writer.write_bits(bits);
 
-        if let Some((width, data)) = code.extra_length() {
-            writer.write_bits(Bits { data, width });
+    // Each time a leaf is selected, its width grows by one.
+    let mut stack = items[..2 * leaves.len() - 2]
+        .iter()
+        .map(|(_, node)| *node)
+        .collect::<Vec<_>>();
+    while let Some(node) = stack.pop() {
+        match nodes[node] {
+            Node::Leaf(symbol) => widthes[symbol] += 1,
+            Node::Package(a, b) => stack.extend(&[a, b]),
         }
-        if let Some((code, width, data)) = code.distance_code() {
-            let dcode = self.distance[code as usize];
-            let bits = Bits::from(dcode);
-            writer.write_bits(bits);
-            writer.write_bits(Bits { data, width });
+    }
+    Ok(widthes)
+}
+pub fn canonical_codes(widthes: &[u8]) -> Vec<Bits> {
+    let mut codes = vec![Bits::new(0, 0); widthes.len()];
+    let mut symbols = widthes
+        .iter()
+        .enumerate()
+        .filter(|(_, width)| **width > 0)
+        .map(|(symbol, width)| (symbol, *width))
+        .collect::<Vec<_>>();
+    symbols.sort_by_key(|x| x.1);
+
+    let mut data = 0u32;
+    let mut prev_width = 0;
+    for (symbol, width) in symbols {
+        data <<= width - prev_width;
+        codes[symbol] = Bits::new(data as u16, width);
+        data += 1;
+        prev_width = width;
+    }
+    codes
+}
+pub(crate) fn write_block(
+    writer: &mut BitWriter,
+    v_in: &[Token],
+    btype: BlockType,
+    bfinal: bool,
+) -> BlockStats {
+    let mut block_stats = BlockStats::new(btype);
+    let start = writer.position();
+    writer.write_bits(Bits::new(bfinal as u16, 1));
+    writer.write_bits(Bits::new(btype as u16, 2));
+    let encoder = HuffmanEncoder::new(btype, v_in, writer);
+    block_stats.header_size = writer.position() - start;
+    for token in v_in {
+        match token.is_pointer() {
+            true => block_stats.matches += 1,
+            false => block_stats.literals += 1,
         }
+        encoder.encode(writer, *token);
     }
+    encoder.encode(writer, Token::END_OF_BLOCK);
+    block_stats.payload_size = writer.position() - start - block_stats.header_size;
+    block_stats
+}
+pub(crate) fn split_blocks(v_in: &[Token]) -> Vec<Token> {
+    // Chunks are merged with the current block while one table costs less than two.
+    let v_in = v_in
+        .iter()
+        .filter(|token| **token != Token::END_OF_BLOCK)
+        .copied()
+        .collect::<Vec<_>>();
+    let mut chunks = v_in.chunks(SPLIT_LEN);
+    let mut v_out = Vec::with_capacity(v_in.len() + v_in.len() / SPLIT_LEN + 1);
+    let first = chunks.next().unwrap_or(&[]);
+    let mut block = frequencies(first);
+    let mut cost = dynamic_cost(&block.0, &block.1);
+    v_out.extend(first);
+    for chunk in chunks {
+        let next = frequencies(chunk);
+        let mut merged = block;
+        merged.0.iter_mut().zip(&next.0).for_each(|(x, y)| *x += y);
+        merged.1.iter_mut().zip(&next.1).for_each(|(x, y)| *x += y);
+        merged.0[END_OF_BLOCK as usize] -= 1;
+        let merged_cost = dynamic_cost(&merged.0, &merged.1);
+        let next_cost = dynamic_cost(&next.0, &next.1);
+        if merged_cost <= cost + next_cost {
+            block = merged;
+            cost = merged_cost;
+        } else {
+            v_out.push(Token::END_OF_BLOCK);
+            block = next;
+            cost = next_cost;
+        }
+        v_out.extend(chunk);
+    }
+    v_out
 }
 // Main functions.
-pub fn huffman_encode(v_in: &[Code], btype: BlockType, buf: &mut [i32; 0x10000]) -> Vec<u8> {
+pub(crate) fn huffman_encode(v_in: &[Token], btype: BlockType) -> Vec<u8> {
+    huffman_encode_blocks(v_in, btype).0
+}
+
+pub(crate) fn huffman_size(v_in: &[Token], btype: BlockType) -> (usize, usize) {
     // Variable Initialization.
-    let mut writer = Writer::new();
+    let (mut header_size, mut payload_size) = (0, 0);
+
+    let v_in = match v_in.last() {
+        Some(&Token::END_OF_BLOCK) => &v_in[..v_in.len() - 1],
+        _ => v_in,
+    };
+
+    // Algorithms.
+    for block in v_in.split(|token| *token == Token::END_OF_BLOCK) {
+        let (lwidthes, dwidthes) = block_widthes(btype, block);
+        header_size += 3;
+        if btype == BlockType::Dynamic {
+            header_size += widthes_size(&lwidthes, &dwidthes);
+        }
+        let tokens = block.iter().chain(std::iter::once(&Token::END_OF_BLOCK));
+        payload_size += tokens
+            .map(|token| code_size(&lwidthes, &dwidthes, *token))
+            .sum::<usize>();
+    }
+    (header_size, payload_size)
+}
+
+pub(crate) fn huffman_encode_blocks(
+    v_in: &[Token],
+    btype: BlockType,
+) -> (Vec<u8>, Vec<BlockStats>) {
+    // Variable Initialization.
+    let mut writer = BitWriter::new();
+    let mut stats = Vec::new();
+
+    let v_in = match v_in.last() {
+        Some(&Token::END_OF_BLOCK) => &v_in[..v_in.len() - 1],
+        _ => v_in,
+    };
+    let mut blocks = v_in.split(|token| *token == Token::END_OF_BLOCK).peekable();
 
     // Algorithms.
-    let data = btype as u16;
-    writer.write_bits(Bits { data: 1, width: 1 });
-    writer.write_bits(Bits { data, width: 2 });
-    let encoder = HuffmanEncoder::new(btype, v_in, buf);
-    for code in v_in {
-        encoder.encode(&mut writer, *code);
-    }
-    writer.finish()
+    while let Some(block) = blocks.next() {
+        let bfinal = blocks.peek().is_none();
+        stats.push(write_block(&mut writer, block, btype, bfinal));
+    }
+    (writer.finish(), stats)
 }
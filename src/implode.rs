@@ -0,0 +1,246 @@
+//! # PKWARE DCL Implode/Explode
+//!
+//! PKWARE Data Compression Library "implode", also known as zip method 10.
+//! It is a LZ77 variant with fixed Shannon-Fano codes.
+//! - LiteralType: literals are Raw bytes or Coded with the literal tree.
+//! - WindowSize: 1 KB, 2 KB or 4 KB sliding window.
+//!
+//! ## Examples
+//!
+//! ### Easy to use.
+//! ```
+//! use devker::prelude::{explode, implode, Cache, LiteralType, WindowSize};
+//!
+//! let mut cache = Cache::new();
+//! let v = String::from("Hello world, this is a wonderful world !");
+//! let v_in = v.into_bytes();
+//!
+//! // Encode.
+//! let encoded = implode(&v_in, LiteralType::Coded, WindowSize::Size4K, &mut cache);
+//! // Decode.
+//! let decoded = explode(&encoded).unwrap();
+//! assert_eq!(v_in, decoded);
+//! ```
+//!
+//! ### Legacy data.
+//! ```
+//! use devker::prelude::explode;
+//!
+//! let encoded = [0x00, 0x04, 0x82, 0x24, 0x25, 0x8f, 0x80, 0x7f];
+//!
+//! let decoded = explode(&encoded).unwrap();
+//! assert_eq!(decoded, b"AIAIAIAIAIAIA");
+//! ```
+
+// Imports.
+use crate::bits::{BitReader, BitWriter, Bits};
+use crate::huffman::{Decoder, Encoder};
+use crate::lzss::{extend, longest_match, PrefixTable};
+use crate::prelude::Cache;
+// Constants.
+const ERROR_DICTIONARY: &str = "Invalid dictionary size.";
+const ERROR_HEADER: &str = "Implode's header is missing.";
+const ERROR_LITERAL: &str = "Invalid literal type.";
+const HEADER_LEN: usize = 2;
+const MIN_LENGTH: usize = 2;
+const MAX_LENGTH: usize = 518;
+const MAX_SHORT_DISTANCE: usize = 0x100;
+const END_OF_STREAM: usize = 519;
+const LENGTH_TABLE: [(u16, u8); 16] = [
+    (3, 0),
+    (2, 0),
+    (4, 0),
+    (5, 0),
+    (6, 0),
+    (7, 0),
+    (8, 0),
+    (9, 0),
+    (10, 1),
+    (12, 2),
+    (16, 3),
+    (24, 4),
+    (40, 5),
+    (72, 6),
+    (136, 7),
+    (264, 8),
+];
+// Code widthes are stored as (count - 1) << 4 | width.
+const LITERAL_WIDTHES: [u8; 98] = [
+    11, 124, 8, 7, 28, 7, 188, 13, 76, 4, 10, 8, 12, 10, 12, 10, 8, 23, 8, 9, 7, 6, 7, 8, 7, 6, 55,
+    8, 23, 24, 12, 11, 7, 9, 11, 12, 6, 7, 22, 5, 7, 24, 6, 11, 9, 6, 7, 22, 7, 11, 38, 7, 9, 8,
+    25, 11, 8, 11, 9, 12, 8, 12, 5, 38, 5, 38, 5, 11, 7, 5, 6, 21, 6, 10, 53, 8, 7, 24, 10, 27, 44,
+    253, 253, 253, 252, 252, 252, 13, 12, 45, 12, 45, 12, 61, 12, 45, 44, 173,
+];
+const LENGTH_WIDTHES: [u8; 6] = [2, 35, 36, 53, 38, 23];
+const DISTANCE_WIDTHES: [u8; 7] = [2, 20, 53, 230, 247, 151, 248];
+// Structures.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LiteralType {
+    Raw = 0,
+    Coded = 1,
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowSize {
+    Size1K = 4,
+    Size2K = 5,
+    Size4K = 6,
+}
+struct ImplodeEncoder {
+    literal: Encoder,
+    length: Encoder,
+    distance: Encoder,
+}
+// Implementations.
+impl ImplodeEncoder {
+    fn new() -> Self {
+        Self {
+            literal: Encoder::unchecked(&expand(&LITERAL_WIDTHES)),
+            length: Encoder::unchecked(&expand(&LENGTH_WIDTHES)),
+            distance: Encoder::unchecked(&expand(&DISTANCE_WIDTHES)),
+        }
+    }
+    fn write_code(writer: &mut BitWriter, encoder: &Encoder, symbol: usize) {
+        // Codes are stored with inverted bits.
+        let mut bits = encoder.code(symbol);
+        bits.data ^= (1 << bits.width) - 1;
+        writer.write_bits(bits);
+    }
+    fn write_literal(&self, writer: &mut BitWriter, literal: u8, ltype: LiteralType) {
+        writer.write_bits(Bits { data: 0, width: 1 });
+        match ltype {
+            LiteralType::Raw => writer.write_bits(Bits {
+                data: literal as u16,
+                width: 8,
+            }),
+            LiteralType::Coded => Self::write_code(writer, &self.literal, literal as usize),
+        }
+    }
+    fn write_length(&self, writer: &mut BitWriter, length: usize) {
+        let symbol = LENGTH_TABLE
+            .iter()
+            .position(|(base, width)| {
+                let base = *base as usize;
+                (base..base + (1 << width)).contains(&length)
+            })
+            .unwrap();
+        let (base, width) = LENGTH_TABLE[symbol];
+        writer.write_bits(Bits { data: 1, width: 1 });
+        Self::write_code(writer, &self.length, symbol);
+        let data = (length - base as usize) as u16;
+        writer.write_bits(Bits { data, width });
+    }
+    fn write_pointer(
+        &self,
+        writer: &mut BitWriter,
+        distance: usize,
+        length: usize,
+        wsize: WindowSize,
+    ) {
+        self.write_length(writer, length);
+        let width = if length == MIN_LENGTH { 2 } else { wsize as u8 };
+        let distance = distance - 1;
+        Self::write_code(writer, &self.distance, distance >> width);
+        let data = (distance & ((1 << width) - 1)) as u16;
+        writer.write_bits(Bits { data, width });
+    }
+}
+// Functions.
+fn expand(compact: &[u8]) -> Vec<u8> {
+    compact
+        .iter()
+        .flat_map(|x| std::iter::repeat_n(x & 0x0F, (x >> 4) as usize + 1))
+        .collect()
+}
+fn get_code(reader: &mut BitReader, decoder: &Decoder) -> Result<usize, String> {
+    // Codes are stored with inverted bits, and they are complete.
+    let max_width = decoder.max_width();
+    let code = reader.peek_bits(max_width) ^ ((1 << max_width) - 1);
+    let bits = decoder.lookup(code as u16).unwrap();
+    reader.skip_bits(bits.width)?;
+    Ok(bits.data as usize)
+}
+// Main functions.
+pub fn implode(v_in: &[u8], ltype: LiteralType, wsize: WindowSize, cache: &mut Cache) -> Vec<u8> {
+    // Variable initialization.
+    let window = 1 << (6 + wsize as usize);
+    let encoder = ImplodeEncoder::new();
+    let mut prefix_table = PrefixTable::new(cache.inner_mut());
+    let mut writer = BitWriter::new();
+    let mut i = 0;
+
+    // Algorithm.
+    writer.write_bits(Bits {
+        data: ltype as u16,
+        width: 8,
+    });
+    writer.write_bits(Bits {
+        data: wsize as u16,
+        width: 8,
+    });
+    while i + 1 < v_in.len() {
+        let matched = prefix_table.insert(&v_in[i..], i as i32);
+        if let Some(j) = matched.map(|j| j as usize) {
+            let distance = i - j;
+            let length = longest_match(&v_in[j + 2..], distance, MAX_LENGTH - 2) + 2;
+            if distance <= window && (length > MIN_LENGTH || distance <= MAX_SHORT_DISTANCE) {
+                for k in (i..).take(length).skip(1).filter(|k| k + 1 < v_in.len()) {
+                    prefix_table.overwrite(&v_in[k..], k as i32);
+                }
+                encoder.write_pointer(&mut writer, distance, length, wsize);
+                i += length;
+                continue;
+            }
+        }
+        encoder.write_literal(&mut writer, v_in[i], ltype);
+        i += 1;
+    }
+    for x in &v_in[i..] {
+        encoder.write_literal(&mut writer, *x, ltype);
+    }
+    encoder.write_length(&mut writer, END_OF_STREAM);
+    writer.finish()
+}
+
+pub fn explode(v_in: &[u8]) -> Result<Vec<u8>, String> {
+    // Conditions.
+    if v_in.len() < HEADER_LEN {
+        return Err(ERROR_HEADER.into());
+    }
+    let ltype = match v_in[0] {
+        0 => LiteralType::Raw,
+        1 => LiteralType::Coded,
+        _ => return Err(ERROR_LITERAL.into()),
+    };
+    let dwidth = match v_in[1] {
+        4..=6 => v_in[1],
+        _ => return Err(ERROR_DICTIONARY.into()),
+    };
+    // Variable initialization.
+    let literal = Decoder::new(&expand(&LITERAL_WIDTHES))?;
+    let length = Decoder::new(&expand(&LENGTH_WIDTHES))?;
+    let distance = Decoder::new(&expand(&DISTANCE_WIDTHES))?;
+    let mut reader = BitReader::new(&v_in[HEADER_LEN..]);
+    let mut v_out = Vec::new();
+
+    // Algorithm.
+    loop {
+        if reader.read_bits(1)? == 0 {
+            let literal = match ltype {
+                LiteralType::Raw => reader.read_bits(8)? as u8,
+                LiteralType::Coded => get_code(&mut reader, &literal)? as u8,
+            };
+            v_out.push(literal);
+            continue;
+        }
+        let (base, width) = LENGTH_TABLE[get_code(&mut reader, &length)?];
+        let l = base as usize + reader.read_bits(width)? as usize;
+        if l == END_OF_STREAM {
+            break;
+        }
+        let width = if l == MIN_LENGTH { 2 } else { dwidth };
+        let d = get_code(&mut reader, &distance)? << width;
+        let d = d + reader.read_bits(width)? as usize + 1;
+        extend(&mut v_out, d, l)?;
+    }
+    Ok(v_out)
+}
@@ -2,23 +2,85 @@
 //!
 //! * Deflate/Inflate: [`deflate`]
 //! * Zlib: [`zlib`]
+//! * Gzip: [`gzip`]
+//! * PKWARE DCL Implode/Explode: [`implode`]
+//! * LZ77 tokens: [`code`], [`lzss`]
+//! * Canonical Huffman codes: [`huffman`]
+//! * Bit readers and writers: [`bits`]
+//! * Deflate/Zlib/Gzip disassembler: [`disasm`]
+//! * Deflate assembler: [`asm`]
+//! * Encoder statistics: [`stats`]
+//! * Compressed size estimation: [`estimate`]
+//! * Deflate/Zlib/Gzip recompression: [`recompress`]
+//! * Zlib-identical output: [`compat`]
+//! * Deflate/Zlib/Gzip concatenation: [`concat`]
+//! * Deflate/Zlib/Gzip verification: [`verify`]
+//! * Salvage of damaged deflate streams: [`salvage`]
 //!
+//! [`asm`]: asm/index.html
+//! [`bits`]: bits/index.html
+//! [`code`]: code/index.html
+//! [`compat`]: compat/index.html
+//! [`concat`]: concat/index.html
 //! [`deflate`]: deflate/index.html
+//! [`disasm`]: disasm/index.html
+//! [`estimate`]: estimate/index.html
+//! [`gzip`]: gzip/index.html
+//! [`huffman`]: huffman/index.html
+//! [`implode`]: implode/index.html
+//! [`lzss`]: lzss/index.html
+//! [`recompress`]: recompress/index.html
+//! [`salvage`]: salvage/index.html
+//! [`stats`]: stats/index.html
+//! [`verify`]: verify/index.html
 //! [`zlib`]: zlib/index.html
 
 pub mod prelude {
     pub use crate::btype::BlockType;
     pub use crate::cache::Cache;
-    pub use crate::deflate::{deflate, inflate, inflate_to};
-    pub use crate::zlib::{zlib_decode, zlib_decode_to, zlib_encode};
+    pub use crate::code::Code;
+    pub use crate::compat::{deflate_compat, gzip_encode_compat, zlib_encode_compat};
+    pub use crate::concat::{deflate_concat, gzip_concat, zlib_concat};
+    pub use crate::deflate::{
+        deflate, deflate_append, deflate_bound, deflate_stats, deflate_stream, deflate_to,
+        encode_tokens, inflate, inflate_to, inflate_to_with, inflate_tokens, inflate_tokens_with,
+        inflate_with, DeflateOptions, InflateOptions, Strategy,
+    };
+    pub use crate::disasm::{disassemble, Disassembly};
+    pub use crate::estimate::{estimate_deflate_size, EstimateOptions};
+    pub use crate::format::Format;
+    pub use crate::gzip::{gzip_decode, gzip_decode_with, gzip_encode, gzip_encode_stream};
+    pub use crate::implode::{explode, implode, LiteralType, WindowSize};
+    pub use crate::lzss::{lzss_decode, lzss_encode, lzss_encode_with};
+    pub use crate::recompress::recompress;
+    pub use crate::salvage::{inflate_salvage, Salvage};
+    pub use crate::stats::Stats;
+    pub use crate::verify::{verify_deflate, verify_gzip, verify_zlib, Verified};
+    pub use crate::zlib::{
+        zlib_decode, zlib_decode_to, zlib_decode_to_with, zlib_decode_with, zlib_encode,
+        zlib_encode_append, zlib_encode_stats, zlib_encode_stream, zlib_encode_to,
+    };
 }
 
 mod adler32;
-mod bits;
+pub mod asm;
+pub mod bits;
 pub mod btype;
 pub mod cache;
-mod code;
+pub mod code;
+pub mod compat;
+pub mod concat;
+mod crc32;
 pub mod deflate;
-mod huffman;
-mod lzss;
+pub mod disasm;
+pub mod estimate;
+pub mod format;
+pub mod gzip;
+pub mod huffman;
+pub mod implode;
+pub mod lzss;
+pub mod recompress;
+pub mod salvage;
+pub mod stats;
+pub mod verify;
 pub mod zlib;
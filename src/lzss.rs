@@ -1,23 +1,223 @@
 //! # LZSS
+//!
+//! Greedy LZ77 parse of bytes into [`Code`] tokens, and the way back.
+//!
+//! The parse slides over its input: only the last 32 KB, and the next 259
+//! bytes to look ahead, are kept. Positions in the prefix table are rebased
+//! when the window slides, so the input can be of any size.
+//!
+//! Deflate's strategies change the parse: Huffman-only emits literals, RLE
+//! only runs of the previous byte, and filtered drops matches shorter than 6.
+//!
+//! Matches are searched by a [`MatchFinder`]: given the window and a position,
+//! it returns the best match (or all of them), and keeps track of the
+//! positions it has seen. The crate ships:
+//! - [`PrefixTable`]: the last position of each 2-byte prefix, the default one.
+//! - [`HashChain`]: every position of each 3-byte hash, searched up to a limit.
+//! - [`BinaryTree`]: positions of each 3-byte hash sorted in a binary tree
+//!   (bt4-style), every longer match is found in logarithmic time.
+//!
+//! ## Examples
+//!
+//! ### Match finders.
+//! ```
+//! use devker::lzss::{lzss_encode_with, HashChain, PrefixTable};
+//! use devker::prelude::{encode_tokens, inflate, lzss_decode, BlockType, Cache};
+//!
+//! let mut cache = Cache::new();
+//! let v_in = "Hello world, this is a wonderful world !".repeat(100).into_bytes();
+//!
+//! // Same Huffman's coding, different match finders.
+//! let tokens = lzss_encode_with(&v_in, &mut PrefixTable::new(cache.inner_mut()));
+//! let prefix = encode_tokens(&tokens, BlockType::Dynamic).unwrap();
+//! let tokens = lzss_encode_with(&v_in, &mut HashChain::new(64));
+//! assert_eq!(lzss_decode(&tokens).unwrap(), v_in);
+//! let chain = encode_tokens(&tokens, BlockType::Dynamic).unwrap();
+//! assert!(chain.len() <= prefix.len());
+//! assert_eq!(inflate(&chain, &mut cache).unwrap(), v_in);
+//! ```
+//!
+//! ### All matches.
+//! ```
+//! use devker::lzss::{lzss_encode_with, BinaryTree, MatchFinder};
+//! use devker::prelude::lzss_decode;
+//!
+//! // Older matches are kept only if they are longer. Positions are sorted
+//! // by their next 258 bytes, so the window holds them.
+//! let window = [&b"abcdefgh-abcdef-abcd-abcdefgh"[..], &[b'.'; 258]].concat();
+//! let mut finder = BinaryTree::new(32);
+//! for i in 0..21 {
+//!     finder.skip(&window, i);
+//! }
+//! let mut matches = Vec::new();
+//! finder.find_all(&window, 21, 8, &mut matches);
+//! let lengths = matches.iter().map(|x| (x.distance, x.length)).collect::<Vec<_>>();
+//! assert_eq!(lengths, [(5, 4), (12, 6), (21, 8)]);
+//!
+//! let v_in = "Hello world, this is a wonderful world !".repeat(100).into_bytes();
+//! let tokens = lzss_encode_with(&v_in, &mut BinaryTree::new(32));
+//! assert_eq!(lzss_decode(&tokens).unwrap(), v_in);
+//! ```
+//!
+//! ### Out of bounds matches.
+//! ```
+//! use devker::lzss::{lzss_encode_with, Match, MatchFinder};
+//! use devker::prelude::{lzss_decode, Code};
+//!
+//! // Every match reaches before the window, and is too long.
+//! struct Broken;
+//! impl MatchFinder for Broken {
+//!     fn find(&mut self, _window: &[u8], i: usize, max_length: usize) -> Option<Match> {
+//!         Some(Match { distance: i + 1, length: max_length + 1 })
+//!     }
+//!     fn skip(&mut self, _window: &[u8], _i: usize) {}
+//!     fn rebase(&mut self, _shift: usize) {}
+//! }
+//!
+//! let v_in = b"Hello world, this is a wonderful world !".to_vec();
+//! let tokens = lzss_encode_with(&v_in, &mut Broken);
+//! assert!(tokens.iter().all(|x| matches!(x, Code::Literal(_))));
+//! assert_eq!(lzss_decode(&tokens).unwrap(), v_in);
+//!
+//! // And so are pointers out of 1..=32768 when decoding.
+//! let mut tokens = vec![Code::Literal(b'a'); 40000];
+//! tokens.push(Code::Pointer { distance: 0, length: 0 });
+//! assert!(lzss_decode(&tokens).is_err());
+//! tokens.pop();
+//! tokens.push(Code::Pointer { distance: 32769, length: 0 });
+//! assert!(lzss_decode(&tokens).is_err());
+//! ```
+//!
+//! ### Custom match finder.
+//! ```
+//! use devker::lzss::{longest_match, lzss_encode_with, Match, MatchFinder};
+//! use devker::prelude::{lzss_decode, Code};
+//!
+//! // Records of 16 bytes: only compare with the previous record.
+//! struct Stride(usize);
+//! impl MatchFinder for Stride {
+//!     fn find(&mut self, window: &[u8], i: usize, max_length: usize) -> Option<Match> {
+//!         let distance = self.0;
+//!         if i < distance {
+//!             return None;
+//!         }
+//!         let length = longest_match(&window[i - distance..], distance, max_length);
+//!         Some(Match { distance, length })
+//!     }
+//!     fn skip(&mut self, _window: &[u8], _i: usize) {}
+//!     fn rebase(&mut self, _shift: usize) {}
+//! }
+//!
+//! let v_in = b"record 0001 abcdrecord 0002 abcdrecord 0003 abcd".to_vec();
+//! let tokens = lzss_encode_with(&v_in, &mut Stride(16));
+//! assert!(tokens.iter().any(|code| matches!(code, Code::Pointer { distance: 16, .. })));
+//! assert_eq!(lzss_decode(&tokens).unwrap(), v_in);
+//! ```
+//!
+//! ### Growing window.
+//! ```
+//! use devker::lzss::{lzss_encode_with, BinaryTree, MatchFinder};
+//! use devker::prelude::lzss_decode;
+//!
+//! let mut x = 1u32;
+//! let mut random = || {
+//!     x = x.wrapping_mul(1103515245).wrapping_add(12345);
+//!     b'a' + (x >> 16) as u8 % 2
+//! };
+//!
+//! // Low-entropy bytes, over one chunk, searched deep.
+//! let v_in: Vec<u8> = (0..140_000).map(|_| random()).collect();
+//! let tokens = lzss_encode_with(&v_in, &mut BinaryTree::new(1000));
+//! assert_eq!(lzss_decode(&tokens).unwrap(), v_in);
+//!
+//! // Positions seen near the end of the window are sorted once it has grown.
+//! let window: Vec<u8> = (0..1000)
+//!     .map(|i| if i < 500 { b"abbab"[i % 5] } else { random() })
+//!     .collect();
+//! let mut finder = BinaryTree::new(1000);
+//! for i in 0..498 {
+//!     finder.skip(&window[..500], i);
+//! }
+//! for i in 498..997 {
+//!     if let Some(m) = finder.find(&window, i, std::cmp::min(258, 998 - i)) {
+//!         assert_eq!(window[i - m.distance..][..m.length], window[i..][..m.length]);
+//!     }
+//! }
+//! ```
+//!
+//! [`Code`]: ../code/enum.Code.html
+//! [`MatchFinder`]: trait.MatchFinder.html
+//! [`PrefixTable`]: struct.PrefixTable.html
+//! [`HashChain`]: struct.HashChain.html
+//! [`BinaryTree`]: struct.BinaryTree.html
 
 // Import.
-use crate::code::Code;
+use crate::code::{Code, Token};
+use crate::prelude::{Cache, Strategy};
+use std::cmp;
+use std::convert::TryInto;
 // Constants.
 const MAX_WINDOW_LENGTH: usize = 0x8000;
 const MAX_LENGTH: usize = 0x100;
-const ERROR_ENDOFBLOCK: &str = "End Of Block is not supposed to be there.";
+const MAX_MATCH_LENGTH: usize = MAX_LENGTH + 2;
+const LOOKAHEAD_LENGTH: usize = MAX_LENGTH + 3;
+const CHUNK_LENGTH: usize = 0x10000;
+const MIN_LENGTH: usize = 3;
+const FILTERED_MIN_LENGTH: usize = 6;
+const HASH_BITS: usize = 15;
+const ERROR_DISTANCE: &str = "Invalid distance.";
 const ERROR_POSITION: &str = "One distance is greater than current index.";
 // Structures.
-struct PrefixTable<'a>(&'a mut [i32; 0x10000]);
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Match {
+    pub distance: usize,
+    pub length: usize,
+}
+pub trait MatchFinder {
+    // Best match at `i`, at most `max_length` long and 32 KB away, then `i` is
+    // seen. Matches shorter than 3 are ignored, and so are the ones longer
+    // than `max_length` or reaching before the window or further than 32 KB:
+    // the parse emits a literal instead. The bytes must be equal, which isn't
+    // checked.
+    fn find(&mut self, window: &[u8], i: usize, max_length: usize) -> Option<Match>;
+    // Every useful match at `i`, by increasing length.
+    fn find_all(&mut self, window: &[u8], i: usize, max_length: usize, matches: &mut Vec<Match>) {
+        matches.extend(self.find(window, i, max_length));
+    }
+    // `i` is seen without searching, it is inside a match.
+    fn skip(&mut self, window: &[u8], i: usize);
+    // The window has slid: positions are `shift` lower, older ones are gone.
+    fn rebase(&mut self, shift: usize);
+}
+pub struct PrefixTable<'a>(&'a mut [i32; 0x10000]);
+pub struct HashChain {
+    head: Vec<i32>,
+    prev: Vec<i32>,
+    max_chain: usize,
+}
+pub struct BinaryTree {
+    head: Vec<i32>,
+    son: Vec<i32>,
+    max_depth: usize,
+    matches: Vec<Match>,
+    pending: Vec<usize>,
+}
+pub(crate) struct Lzss<'a, F> {
+    finder: F,
+    window: &'a mut Vec<u8>,
+    tokens: &'a mut Vec<Token>,
+    strategy: Strategy,
+    i: usize,
+}
 // Implementations.
 impl<'a> PrefixTable<'a> {
-    fn new(array: &'a mut [i32; 0x10000]) -> Self {
+    pub fn new(array: &'a mut [i32; 0x10000]) -> Self {
         for x in array.iter_mut() {
             *x = -1;
         }
         Self(array)
     }
-    fn insert(&mut self, buf: &[u8], value: i32) -> Option<i32> {
+    pub(crate) fn insert(&mut self, buf: &[u8], value: i32) -> Option<i32> {
         let key = prefix(buf);
         let old = self.0[key];
         self.0[key] = value;
@@ -26,26 +226,392 @@ impl<'a> PrefixTable<'a> {
         }
         None
     }
-    fn overwrite(&mut self, buf: &[u8], value: i32) {
+    pub(crate) fn overwrite(&mut self, buf: &[u8], value: i32) {
         let key = prefix(buf);
         self.0[key] = value;
     }
 }
+impl<'a> MatchFinder for PrefixTable<'a> {
+    fn find(&mut self, window: &[u8], i: usize, max_length: usize) -> Option<Match> {
+        // Only the last position of the prefix is known, short matches aren't kept.
+        let j = self.insert(&window[i..], i as i32)? as usize;
+        let distance = i - j;
+        if distance > MAX_WINDOW_LENGTH {
+            return None;
+        }
+        let len = longest_match(&window[j + 2..], distance, MAX_LENGTH);
+        if len <= 2 {
+            return None;
+        }
+        Some(Match {
+            distance,
+            length: cmp::min(len + 2, max_length),
+        })
+    }
+    fn skip(&mut self, window: &[u8], i: usize) {
+        self.overwrite(&window[i..], i as i32);
+    }
+    fn rebase(&mut self, shift: usize) {
+        let shift = shift as i32;
+        for x in self.0.iter_mut() {
+            *x = if *x >= shift { *x - shift } else { -1 };
+        }
+    }
+}
+impl HashChain {
+    pub fn new(max_chain: usize) -> Self {
+        Self {
+            head: vec![-1; 1 << HASH_BITS],
+            prev: Vec::new(),
+            max_chain,
+        }
+    }
+    fn insert(&mut self, window: &[u8], i: usize) -> i32 {
+        let key = hash(window, i);
+        let old = self.head[key];
+        self.head[key] = i as i32;
+        if self.prev.len() <= i {
+            self.prev.resize(i + 1, -1);
+        }
+        self.prev[i] = old;
+        old
+    }
+}
+impl MatchFinder for HashChain {
+    fn find(&mut self, window: &[u8], i: usize, max_length: usize) -> Option<Match> {
+        // Variable initialization.
+        let mut best = None;
+        let mut longest = MIN_LENGTH - 1;
+        let mut j = self.insert(window, i);
+
+        // Algorithm.
+        for _ in 0..self.max_chain {
+            if j.is_negative() || i - j as usize > MAX_WINDOW_LENGTH || longest == max_length {
+                break;
+            }
+            let distance = i - j as usize;
+            let length = longest_match(&window[j as usize..], distance, max_length);
+            if length > longest {
+                longest = length;
+                best = Some(Match { distance, length });
+            }
+            j = self.prev[j as usize];
+        }
+        best
+    }
+    fn find_all(&mut self, window: &[u8], i: usize, max_length: usize, matches: &mut Vec<Match>) {
+        let mut j = self.insert(window, i);
+        let mut longest = MIN_LENGTH - 1;
+        for _ in 0..self.max_chain {
+            if j.is_negative() || i - j as usize > MAX_WINDOW_LENGTH || longest == max_length {
+                break;
+            }
+            let distance = i - j as usize;
+            let length = longest_match(&window[j as usize..], distance, max_length);
+            if length > longest {
+                longest = length;
+                matches.push(Match { distance, length });
+            }
+            j = self.prev[j as usize];
+        }
+    }
+    fn skip(&mut self, window: &[u8], i: usize) {
+        self.insert(window, i);
+    }
+    fn rebase(&mut self, shift: usize) {
+        let shift = shift as i32;
+        let rebase = |x: &mut i32| *x = if *x >= shift { *x - shift } else { -1 };
+        self.head.iter_mut().for_each(rebase);
+        self.prev.drain(..cmp::min(shift as usize, self.prev.len()));
+        self.prev.iter_mut().for_each(rebase);
+    }
+}
+impl BinaryTree {
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            head: vec![-1; 1 << HASH_BITS],
+            son: Vec::new(),
+            max_depth,
+            matches: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+    fn update(&mut self, window: &[u8], i: usize, max_length: usize, record: bool) {
+        // A position is sorted by its next 258 bytes: until the window holds
+        // them, it is searched but inserted later, in order.
+        let ready = self
+            .pending
+            .iter()
+            .take_while(|j| window.len() - **j >= MAX_MATCH_LENGTH)
+            .count();
+        for k in 0..ready {
+            let j = self.pending[k];
+            self.insert(window, j, MAX_MATCH_LENGTH, false, true);
+        }
+        self.pending.drain(..ready);
+        if self.pending.is_empty() && window.len() - i >= MAX_MATCH_LENGTH {
+            self.insert(window, i, max_length, record, true);
+        } else {
+            if record {
+                self.insert(window, i, max_length, true, false);
+            }
+            self.pending.push(i);
+        }
+    }
+    fn insert(&mut self, window: &[u8], i: usize, max_length: usize, record: bool, link: bool) {
+        // Variable initialization.
+        let key = hash(window, i);
+        let mut j = self.head[key];
+        if link {
+            self.head[key] = i as i32;
+            if self.son.len() < 2 * (i + 1) {
+                self.son.resize(2 * (i + 1), -1);
+            }
+        }
+        // Left and right children of `i` are still to be set: bytes after
+        // the common prefix of smaller and greater nodes are compared.
+        let (mut left, mut right) = (2 * i, 2 * i + 1);
+        let (mut left_len, mut right_len) = (0, 0);
+        let limit = cmp::min(MAX_MATCH_LENGTH, window.len() - i);
+        let mut longest = MIN_LENGTH - 1;
+
+        // Algorithm.
+        for _ in 0..self.max_depth {
+            if j.is_negative() || i - j as usize > MAX_WINDOW_LENGTH {
+                break;
+            }
+            let j_usize = j as usize;
+            let distance = i - j_usize;
+            let mut len = cmp::min(left_len, right_len);
+            len += longest_match(&window[j_usize + len..], distance, limit - len);
+            if record && cmp::min(len, max_length) > longest {
+                longest = cmp::min(len, max_length);
+                self.matches.push(Match {
+                    distance,
+                    length: longest,
+                });
+            }
+            if len == limit {
+                // Same 258 bytes: `i` takes `j`'s place. Only a search ends
+                // earlier, at the end of the window.
+                if link {
+                    self.son[left] = self.son[2 * j_usize];
+                    self.son[right] = self.son[2 * j_usize + 1];
+                }
+                return;
+            }
+            if window[j_usize + len] < window[i + len] {
+                if link {
+                    self.son[left] = j;
+                    left = 2 * j_usize + 1;
+                }
+                j = self.son[2 * j_usize + 1];
+                left_len = len;
+            } else {
+                if link {
+                    self.son[right] = j;
+                    right = 2 * j_usize;
+                }
+                j = self.son[2 * j_usize];
+                right_len = len;
+            }
+        }
+        if link {
+            self.son[left] = -1;
+            self.son[right] = -1;
+        }
+    }
+}
+impl MatchFinder for BinaryTree {
+    fn find(&mut self, window: &[u8], i: usize, max_length: usize) -> Option<Match> {
+        self.matches.clear();
+        self.update(window, i, max_length, true);
+        self.matches.last().copied()
+    }
+    fn find_all(&mut self, window: &[u8], i: usize, max_length: usize, matches: &mut Vec<Match>) {
+        self.matches.clear();
+        self.update(window, i, max_length, true);
+        matches.extend_from_slice(&self.matches);
+    }
+    fn skip(&mut self, window: &[u8], i: usize) {
+        self.update(window, i, MAX_MATCH_LENGTH, false);
+    }
+    fn rebase(&mut self, shift: usize) {
+        self.pending.retain(|j| *j >= shift);
+        self.pending.iter_mut().for_each(|j| *j -= shift);
+        let shift = shift as i32;
+        let rebase = |x: &mut i32| *x = if *x >= shift { *x - shift } else { -1 };
+        self.head.iter_mut().for_each(rebase);
+        self.son
+            .drain(..cmp::min(2 * shift as usize, self.son.len()));
+        self.son.iter_mut().for_each(rebase);
+    }
+}
+impl<F: MatchFinder + ?Sized> MatchFinder for &mut F {
+    fn find(&mut self, window: &[u8], i: usize, max_length: usize) -> Option<Match> {
+        (**self).find(window, i, max_length)
+    }
+    fn find_all(&mut self, window: &[u8], i: usize, max_length: usize, matches: &mut Vec<Match>) {
+        (**self).find_all(window, i, max_length, matches)
+    }
+    fn skip(&mut self, window: &[u8], i: usize) {
+        (**self).skip(window, i)
+    }
+    fn rebase(&mut self, shift: usize) {
+        (**self).rebase(shift)
+    }
+}
+impl<'a> Lzss<'a, PrefixTable<'a>> {
+    pub(crate) fn new(cache: &'a mut Cache, strategy: Strategy) -> Self {
+        let Cache {
+            buf,
+            window,
+            tokens,
+            ..
+        } = cache;
+        Self::with_finder(PrefixTable::new(buf), window, tokens, strategy)
+    }
+}
+impl<'a, F: MatchFinder> Lzss<'a, F> {
+    pub(crate) fn with_finder(
+        finder: F,
+        window: &'a mut Vec<u8>,
+        tokens: &'a mut Vec<Token>,
+        strategy: Strategy,
+    ) -> Self {
+        window.clear();
+        Self {
+            finder,
+            window,
+            tokens,
+            strategy,
+            i: 0,
+        }
+    }
+    pub(crate) fn encode(&mut self, v_in: &[u8], last: bool) -> &[Token] {
+        // Conditions.
+        if self.strategy == Strategy::HuffmanOnly {
+            self.tokens.clear();
+            self.tokens.extend(v_in.iter().map(|x| Token::literal(*x)));
+            return self.tokens;
+        }
+        // Variable initialization.
+        self.window.extend_from_slice(v_in);
+        self.tokens.clear();
+        let v_out = &mut *self.tokens;
+        let buf = &self.window[..];
+        let end = cmp::max(3, buf.len()) - 3;
+        let stop = match last {
+            true => end,
+            false => buf.len().saturating_sub(LOOKAHEAD_LENGTH),
+        };
+        let finder = &mut self.finder;
+        let mut i = self.i;
+        let min_length = match self.strategy {
+            Strategy::Filtered => FILTERED_MIN_LENGTH,
+            _ => MIN_LENGTH,
+        };
+
+        // Algorithm.
+        match self.strategy {
+            Strategy::Rle => rle_encode(buf, &mut i, stop, end, v_out),
+            _ => lz_encode(buf, &mut i, stop, end, min_length, finder, v_out),
+        }
+        if last {
+            v_out.extend(buf[i..].iter().map(|x| Token::literal(*x)));
+            i = buf.len();
+        }
+
+        // Slide the window.
+        self.i = i;
+        if self.i > 2 * MAX_WINDOW_LENGTH {
+            let shift = self.i - MAX_WINDOW_LENGTH;
+            self.window.drain(..shift);
+            self.finder.rebase(shift);
+            self.i -= shift;
+        }
+        self.tokens
+    }
+}
 // Functions.
+fn lz_encode(
+    buf: &[u8],
+    i: &mut usize,
+    stop: usize,
+    end: usize,
+    min_length: usize,
+    finder: &mut impl MatchFinder,
+    v_out: &mut Vec<Token>,
+) {
+    while *i < stop {
+        let max_length = cmp::min(MAX_MATCH_LENGTH, end + 1 - *i);
+        if let Some(Match { distance, length }) = finder.find(buf, *i, max_length) {
+            let max_distance = cmp::min(*i, MAX_WINDOW_LENGTH);
+            if length >= min_length
+                && length <= max_length
+                && (1..=max_distance).contains(&distance)
+            {
+                for k in (*i..).take(length).skip(1) {
+                    finder.skip(buf, k);
+                }
+                *i += length;
+                v_out.push(Token::pointer(distance as u16, (length - 3) as u8));
+                continue;
+            }
+        }
+        v_out.push(Token::literal(buf[*i]));
+        *i += 1;
+    }
+}
+fn rle_encode(buf: &[u8], i: &mut usize, stop: usize, end: usize, v_out: &mut Vec<Token>) {
+    // Only runs of the previous byte, the prefix table isn't needed.
+    while *i < stop {
+        if *i > 0 {
+            let len = longest_match(&buf[*i - 1..], 1, MAX_MATCH_LENGTH);
+            let length = cmp::min(len, end + 1 - *i);
+            if length >= MIN_LENGTH {
+                *i += length;
+                v_out.push(Token::pointer(1, (length - 3) as u8));
+                continue;
+            }
+        }
+        v_out.push(Token::literal(buf[*i]));
+        *i += 1;
+    }
+}
+fn hash(buf: &[u8], i: usize) -> usize {
+    let key = (buf[i] as usize) << 10 ^ (buf[i + 1] as usize) << 5 ^ buf[i + 2] as usize;
+    key & ((1 << HASH_BITS) - 1)
+}
 fn prefix(buf: &[u8]) -> usize {
     let mut array = [0; 8];
-    (&mut array[6..8]).copy_from_slice(&buf[0..2]);
+    array[6..8].copy_from_slice(&buf[0..2]);
     usize::from_be_bytes(array)
 }
-fn longest_match(buf: &[u8], d: usize) -> usize {
-    buf[d..]
+pub fn longest_match(buf: &[u8], d: usize, max: usize) -> usize {
+    // Variable initialization.
+    let max = cmp::min(max, buf.len() - d);
+    let (a, b) = (&buf[d..d + max], &buf[..max]);
+    let mut len = 0;
+
+    // Eight bytes at a time, the first different byte is the lowest set bit.
+    while len + 8 <= max {
+        let x = u64::from_le_bytes(a[len..len + 8].try_into().unwrap());
+        let y = u64::from_le_bytes(b[len..len + 8].try_into().unwrap());
+        let diff = x ^ y;
+        if diff != 0 {
+            return len + (diff.trailing_zeros() / 8) as usize;
+        }
+        len += 8;
+    }
+    // The last bytes.
+    len + a[len..]
         .iter()
-        .zip(buf)
-        .take(MAX_LENGTH)
+        .zip(&b[len..])
         .take_while(|(x, y)| *x == *y)
         .count()
 }
-pub fn extend(buf: &mut Vec<u8>, mut d: usize, mut l: usize) -> Result<(), String> {
+pub(crate) fn extend(buf: &mut Vec<u8>, mut d: usize, mut l: usize) -> Result<(), String> {
     if buf.len() < d {
         return Err(ERROR_POSITION.into());
     }
@@ -56,7 +622,7 @@ pub fn extend(buf: &mut Vec<u8>, mut d: usize, mut l: usize) -> Result<(), Strin
     while l >= d {
         unsafe {
             let len = buf.len();
-            std::ptr::copy_nonoverlapping(buf.get_unchecked(start), buf.get_unchecked_mut(len), d);
+            std::ptr::copy_nonoverlapping(buf.as_ptr().add(start), buf.as_mut_ptr().add(len), d);
             buf.set_len(len + d);
         }
         l -= d;
@@ -66,50 +632,40 @@ pub fn extend(buf: &mut Vec<u8>, mut d: usize, mut l: usize) -> Result<(), Strin
     // Copy the last remaining bytes
     unsafe {
         let len = buf.len();
-        std::ptr::copy_nonoverlapping(buf.get_unchecked(start), buf.get_unchecked_mut(len), l);
+        std::ptr::copy_nonoverlapping(buf.as_ptr().add(start), buf.as_mut_ptr().add(len), l);
         buf.set_len(len + l);
     }
 
     Ok(())
 }
+fn encode_chunks(v_in: &[u8], lzss: &mut Lzss<impl MatchFinder>) -> Vec<Code> {
+    let mut v_out = Vec::new();
+    let mut chunks = v_in.chunks(CHUNK_LENGTH).peekable();
+    while let Some(chunk) = chunks.next() {
+        let tokens = lzss.encode(chunk, chunks.peek().is_none());
+        v_out.extend(tokens.iter().map(|token| Code::from(*token)));
+    }
+    v_out
+}
 // Main functions.
-pub fn lzss_encode(v_in: &[u8], buf: &mut [i32; 0x10000]) -> Vec<Code> {
+pub fn lzss_encode(v_in: &[u8], cache: &mut Cache) -> Vec<Code> {
     // Variable initialization.
-    let end = std::cmp::max(3, v_in.len()) - 3;
-    let mut prefix_table = PrefixTable::new(buf);
-    let mut v_out = Vec::new();
-    let mut i = 0;
+    let mut lzss = Lzss::new(cache, Strategy::Greedy);
 
     // Algorithm.
-    while i < end {
-        let matched = prefix_table.insert(&v_in[i..], i as i32);
-        if let Some(j) = matched.map(|j| j as usize) {
-            let distance = i - j;
-            if distance <= MAX_WINDOW_LENGTH {
-                let len = longest_match(&v_in[j + 2..], distance);
-                let length = std::cmp::min(len + 2, end + 1 - i);
-                if len > 2 {
-                    for k in (i..).take(length).skip(1) {
-                        prefix_table.overwrite(&v_in[k..], k as i32);
-                    }
-                    i += length;
-                    let distance = distance as u16;
-                    let length = (length - 3) as u8;
-                    v_out.push(Code::Pointer { distance, length });
-                    continue;
-                }
-            }
-        }
-        v_out.push(Code::Literal(v_in[i]));
-        i += 1;
-    }
-    for x in &v_in[i..] {
-        v_out.push(Code::Literal(*x));
-    }
-    v_out
+    encode_chunks(v_in, &mut lzss)
+}
+
+pub fn lzss_encode_with(v_in: &[u8], finder: &mut impl MatchFinder) -> Vec<Code> {
+    // Variable initialization.
+    let mut window = Vec::new();
+    let mut tokens = Vec::new();
+    let mut lzss = Lzss::with_finder(finder, &mut window, &mut tokens, Strategy::Greedy);
+
+    // Algorithm.
+    encode_chunks(v_in, &mut lzss)
 }
 
-#[allow(dead_code)]
 pub fn lzss_decode(v_in: &[Code]) -> Result<Vec<u8>, String> {
     // Variable initialization.
     let mut v_out = Vec::new();
@@ -117,12 +673,17 @@ pub fn lzss_decode(v_in: &[Code]) -> Result<Vec<u8>, String> {
     // Algorithm.
     for &code in v_in {
         match code {
-            Code::EndOfBlock => return Err(ERROR_ENDOFBLOCK.into()),
+            Code::EndOfBlock => (),
             Code::Literal(a) => v_out.push(a),
             Code::Pointer {
                 distance: d,
                 length: l,
-            } => extend(&mut v_out, d as usize, l as usize + 3)?,
+            } => {
+                if d == 0 || d as usize > MAX_WINDOW_LENGTH {
+                    return Err(ERROR_DISTANCE.into());
+                }
+                extend(&mut v_out, d as usize, l as usize + 3)?
+            }
         }
     }
     Ok(v_out)
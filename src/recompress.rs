@@ -0,0 +1,220 @@
+//! # Recompress
+//!
+//! Decode a deflate, zlib or gzip stream and encode it again with dynamic
+//! blocks.
+//! - Reparse: LZSS runs again on the decoded data, while it is decoded. Levels
+//!   1 to 6 use a hash chain and levels 7 to 9 a binary tree, which search
+//!   deeper as the level grows. Only the window is kept, and a block is
+//!   written every 64 KB.
+//! - KeepTokens: the original LZ77 tokens are kept, only the tables and the
+//!   block splits are rebuilt, where new tables pay for themselves. It is
+//!   cheaper.
+//!
+//! The wrapper is kept as is: the header, and the checksums which are
+//! verified on the way. Every member of a gzip stream is recompressed. If the
+//! stream doesn't get smaller, it is returned as is.
+//!
+//! ## Examples
+//!
+//! ### Easy to use.
+//! ```
+//! use devker::prelude::{
+//!     recompress, zlib_decode, zlib_encode, BlockType, Cache, Format, InflateOptions,
+//! };
+//! use devker::recompress::RecompressMode;
+//!
+//! let mut cache = Cache::new();
+//! let options = InflateOptions::default();
+//! let v_in = "Hello world, this is a wonderful world !".repeat(100).into_bytes();
+//! let encoded = zlib_encode(&v_in, BlockType::Fixed, &mut cache);
+//!
+//! let mode = RecompressMode::KeepTokens;
+//! let recompressed = recompress(&encoded, Format::Zlib, mode, options, &mut cache).unwrap();
+//! assert!(recompressed.len() < encoded.len());
+//! assert_eq!(zlib_decode(&recompressed, &mut cache).unwrap(), v_in);
+//! ```
+//!
+//! ### Reparse.
+//! ```
+//! use devker::prelude::{deflate, inflate, recompress, BlockType, Cache, Format, InflateOptions};
+//! use devker::recompress::RecompressMode;
+//!
+//! let mut cache = Cache::new();
+//! let options = InflateOptions::default();
+//! let v_in = "Hello world, this is a wonderful world !".repeat(5000).into_bytes();
+//! let encoded = deflate(&v_in, BlockType::Fixed, &mut cache);
+//!
+//! for level in 1..=9 {
+//!     let mode = RecompressMode::Reparse(level);
+//!     let recompressed = recompress(&encoded, Format::Deflate, mode, options, &mut cache);
+//!     let recompressed = recompressed.unwrap();
+//!     assert!(recompressed.len() < encoded.len());
+//!     assert_eq!(inflate(&recompressed, &mut cache).unwrap(), v_in);
+//! }
+//! let mode = RecompressMode::Reparse(10);
+//! assert!(recompress(&encoded, Format::Deflate, mode, options, &mut cache).is_err());
+//! ```
+//!
+//! ### Gzip members.
+//! ```
+//! use devker::prelude::{
+//!     gzip_decode, gzip_encode, recompress, BlockType, Cache, Format, InflateOptions,
+//! };
+//! use devker::recompress::RecompressMode;
+//!
+//! let mut cache = Cache::new();
+//! let options = InflateOptions::default();
+//! let first = "Hello world, ".repeat(100).into_bytes();
+//! let second = "this is a wonderful world !".repeat(100).into_bytes();
+//! let mut encoded = gzip_encode(&first, BlockType::Raw, &mut cache);
+//! encoded.extend(gzip_encode(&second, BlockType::Raw, &mut cache));
+//!
+//! let mode = RecompressMode::Reparse(6);
+//! let recompressed = recompress(&encoded, Format::Gzip, mode, options, &mut cache).unwrap();
+//! assert!(recompressed.len() < encoded.len());
+//! assert_eq!(gzip_decode(&recompressed, &mut cache).unwrap(), [first, second].concat());
+//!
+//! encoded[25] ^= 1;
+//! let error = recompress(&encoded, Format::Gzip, RecompressMode::KeepTokens, options, &mut cache);
+//! assert_eq!(error.unwrap_err(), "Gzip checksum error");
+//! ```
+
+// Imports.
+use crate::adler32::Adler32;
+use crate::code::Token;
+use crate::crc32::Crc32;
+use crate::deflate::{inflate_tokens_len, inflate_window, Deflater};
+use crate::huffman::{huffman_encode, split_blocks};
+use crate::lzss::{lzss_decode, BinaryTree, HashChain, Lzss, MatchFinder};
+use crate::prelude::{BlockType, Cache, Format, InflateOptions, Strategy};
+use crate::{gzip, zlib};
+// Constants.
+const ERROR_LENGTH: &str = "Stream is too short.";
+const ERROR_LEVEL: &str = "Compression level must be between 1 and 9.";
+// Chain length of the hash chain for levels 1 to 6, then depth of the binary tree for levels 7 to 9.
+const EFFORTS: [usize; 9] = [4, 8, 16, 32, 64, 128, 16, 32, 64];
+// Structures.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecompressMode {
+    Reparse(u8),
+    KeepTokens,
+}
+// Functions.
+fn reparse(
+    v_in: &[u8],
+    finder: impl MatchFinder,
+    options: InflateOptions,
+    cache: &mut Cache,
+    mut update: impl FnMut(&[u8]),
+) -> Result<(Vec<u8>, usize), String> {
+    // The decoder's tables and the encoder's window are different parts of the cache.
+    let strict = !options.permissive;
+    let Cache {
+        buf,
+        window,
+        tokens,
+        ..
+    } = cache;
+    let lzss = Lzss::with_finder(finder, window, tokens, Strategy::Greedy);
+    let mut deflater = Deflater::new(lzss, BlockType::Dynamic);
+    let (_, consumed) = inflate_window(v_in, buf, strict, |x| {
+        update(x);
+        deflater.push(x);
+    })?;
+    Ok((deflater.finish(), consumed))
+}
+fn recompress_data(
+    v_in: &[u8],
+    mode: RecompressMode,
+    options: InflateOptions,
+    cache: &mut Cache,
+    mut update: impl FnMut(&[u8]),
+) -> Result<(Vec<u8>, usize), String> {
+    match mode {
+        RecompressMode::Reparse(level) if level <= 6 => {
+            let finder = HashChain::new(EFFORTS[level as usize - 1]);
+            reparse(v_in, finder, options, cache, update)
+        }
+        RecompressMode::Reparse(level) => {
+            let finder = BinaryTree::new(EFFORTS[level as usize - 1]);
+            reparse(v_in, finder, options, cache, update)
+        }
+        RecompressMode::KeepTokens => {
+            let (codes, consumed) = inflate_tokens_len(v_in, options, cache)?;
+            update(&lzss_decode(&codes)?);
+            let tokens = codes.into_iter().map(Token::from).collect::<Vec<_>>();
+            Ok((
+                huffman_encode(&split_blocks(&tokens), BlockType::Dynamic),
+                consumed,
+            ))
+        }
+    }
+}
+// Main functions.
+pub fn recompress(
+    v_in: &[u8],
+    format: Format,
+    mode: RecompressMode,
+    options: InflateOptions,
+    cache: &mut Cache,
+) -> Result<Vec<u8>, String> {
+    // Conditions.
+    if let RecompressMode::Reparse(level) = mode {
+        if !(1..=9).contains(&level) {
+            return Err(ERROR_LEVEL.into());
+        }
+    }
+    // Variable initialization.
+    let trailer_len = match format {
+        Format::Deflate => 0,
+        Format::Zlib => zlib::ADLER_LEN,
+        Format::Gzip => gzip::TRAILER_LEN,
+    };
+    let mut v_out = Vec::with_capacity(v_in.len());
+    let mut pos = 0;
+
+    // Algorithm.
+    loop {
+        let header_len = match format {
+            Format::Deflate => 0,
+            Format::Zlib => zlib::read_header(&v_in[pos..])?,
+            Format::Gzip => gzip::read_header(&v_in[pos..])?,
+        };
+        let mut adler32 = Adler32::new();
+        let mut crc32 = Crc32::new();
+        let mut len = 0;
+        let data = &v_in[pos + header_len..];
+        let (encoded, consumed) = recompress_data(data, mode, options, cache, |x| {
+            match format {
+                Format::Deflate => (),
+                Format::Zlib => adler32.update(x),
+                Format::Gzip => crc32.update(x),
+            }
+            len += x.len();
+        })?;
+        v_out.extend(&v_in[pos..pos + header_len]);
+        v_out.extend(encoded);
+        pos += header_len + consumed;
+        let trailer = match v_in.get(pos..pos + trailer_len) {
+            Some(trailer) => trailer,
+            None => return Err(ERROR_LENGTH.into()),
+        };
+        match format {
+            Format::Deflate => (),
+            Format::Zlib => zlib::check_checksum(trailer, adler32.checksum())?,
+            Format::Gzip => gzip::check_checksum(trailer, crc32.checksum(), len)?,
+        }
+        v_out.extend(trailer);
+        pos += trailer_len;
+        // Gzip members follow each other up to the end.
+        if format != Format::Gzip || pos == v_in.len() {
+            break;
+        }
+    }
+    // Whatever follows the stream is kept as is.
+    v_out.extend(&v_in[pos..]);
+    if v_out.len() >= v_in.len() {
+        return Ok(v_in.to_vec());
+    }
+    Ok(v_out)
+}
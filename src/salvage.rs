@@ -0,0 +1,187 @@
+//! # Salvage
+//!
+//! Lenient inflate for damaged streams. Everything decoded before an error is
+//! kept, and the error is returned with its bit offset.
+//!
+//! With resync, the decoder then scans forward bit by bit for a plausible
+//! block: one which decodes to its end and is followed by another one, or is
+//! the last block and ends the input (give or take a gzip trailer). Most
+//! offsets are rejected on their header alone: a reserved type, LEN which
+//! isn't the complement of NLEN, or counts and code lengths out of range.
+//! Decoding goes on from there and the lost part is recorded as a gap.
+//! Matches which reach over a gap copy the wrong bytes: the output after a
+//! gap may be damaged too.
+//!
+//! ## Examples
+//!
+//! ### Truncated stream.
+//! ```
+//! use devker::prelude::{deflate, inflate_salvage, BlockType, Cache, InflateOptions};
+//!
+//! let mut cache = Cache::new();
+//! let options = InflateOptions::default();
+//! let v_in = b"Hello world, this is a wonderful world !".to_vec();
+//! let encoded = deflate(&v_in, BlockType::Raw, &mut cache);
+//!
+//! let salvage = inflate_salvage(&encoded[..20], false, options, &mut cache);
+//! assert_eq!(salvage.data, &v_in[..15]);
+//! assert_eq!(salvage.gaps.len(), 1);
+//! assert_eq!(salvage.gaps[0].resume, None);
+//! ```
+//!
+//! ### Resync.
+//! ```
+//! use devker::prelude::{
+//!     deflate, deflate_concat, inflate_salvage, BlockType, Cache, InflateOptions,
+//! };
+//!
+//! let mut cache = Cache::new();
+//! let options = InflateOptions::default();
+//! let first = deflate(b"Hello world, ", BlockType::Fixed, &mut cache);
+//! let second = deflate(b"this is a wonderful world !", BlockType::Raw, &mut cache);
+//! let mut encoded = deflate_concat(&[&first, &second], &mut cache).unwrap();
+//! encoded[0] |= 0b110;
+//!
+//! let salvage = inflate_salvage(&encoded, true, options, &mut cache);
+//! assert_eq!(salvage.gaps.len(), 1);
+//! assert_eq!(salvage.gaps[0].error, "Reserved btype.");
+//! assert!(salvage.gaps[0].resume.is_some());
+//! assert!(salvage.data.ends_with(b"this is a wonderful world !"));
+//! ```
+
+// Imports.
+use crate::bits::BitReader;
+use crate::deflate::{self, check_header, HuffmanDecoder, Inflater, Sink};
+use crate::prelude::{Cache, InflateOptions};
+// Constants.
+const MAX_TRAILER_LEN: usize = 8;
+// Structures.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gap {
+    pub error: String,
+    pub block: usize,
+    pub offset: usize,
+    pub position: usize,
+    pub resume: Option<usize>,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Salvage {
+    pub data: Vec<u8>,
+    pub gaps: Vec<Gap>,
+}
+// Inflates like inflate, but a truncated stored block is kept up to the end.
+struct Salvager<'a, F: FnMut(&[u8])>(Inflater<'a, Vec<u8>, F>);
+// Implementations.
+impl<'a, F: FnMut(&[u8])> Sink for Salvager<'a, F> {
+    fn stored(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.0.stored(bytes)
+    }
+    fn truncated(&mut self, bytes: &[u8]) {
+        // A vector always has room.
+        let _ = self.0.stored(bytes);
+    }
+    fn huffman(&mut self, decoder: &HuffmanDecoder, reader: &mut BitReader) -> Result<(), String> {
+        self.0.huffman(decoder, reader)
+    }
+}
+// Functions.
+// Decodes one block after the first `len` bytes of `v_out`, everything decoded is kept up to an error. The vector
+// isn't truncated, so that a failed candidate doesn't have to zero it again.
+fn read_block(
+    reader: &mut BitReader,
+    buf: &mut [i32; 0x10000],
+    v_out: &mut Vec<u8>,
+    len: &mut usize,
+    strict: bool,
+) -> Result<bool, String> {
+    let mut salvager = Salvager(Inflater::new(v_out, *len, |_| ()));
+    let bfinal = deflate::read_block(reader, buf, strict, &mut salvager);
+    *len = salvager.0.len();
+    bfinal
+}
+fn find_block<'a>(
+    v_in: &'a [u8],
+    start: usize,
+    buf: &mut [i32; 0x10000],
+    v_out: &mut Vec<u8>,
+    len: &mut usize,
+    strict: bool,
+) -> Option<(usize, BitReader<'a>, bool)> {
+    let start_len = *len;
+    for offset in start..v_in.len() * 8 {
+        let mut reader = BitReader::new(&v_in[offset / 8..]);
+        reader.skip_bits((offset % 8) as u8).ok()?;
+        // Most offsets are rejected on their header, before any table is built.
+        if check_header(&mut reader.clone(), strict).is_err() {
+            continue;
+        }
+        let mut bfinal = read_block(&mut reader, buf, v_out, len, strict);
+        if let Ok(false) = bfinal {
+            bfinal = read_block(&mut reader, buf, v_out, len, strict);
+        }
+        if let Ok(bfinal) = bfinal {
+            let end = offset / 8 + reader.position().div_ceil(8);
+            if !bfinal || end + MAX_TRAILER_LEN >= v_in.len() {
+                return Some((offset, reader, bfinal));
+            }
+        }
+        *len = start_len;
+    }
+    None
+}
+// Main functions.
+pub fn inflate_salvage(
+    v_in: &[u8],
+    resync: bool,
+    options: InflateOptions,
+    cache: &mut Cache,
+) -> Salvage {
+    // Variable Initialization.
+    let strict = !options.permissive;
+    let buf = cache.inner_mut();
+    let mut reader = BitReader::new(v_in);
+    let mut base = 0;
+    let mut len = 0;
+    let mut salvage = Salvage {
+        data: Vec::new(),
+        gaps: Vec::new(),
+    };
+
+    // Algorithms.
+    loop {
+        let block = base + reader.position();
+        let error = match read_block(&mut reader, buf, &mut salvage.data, &mut len, strict) {
+            Ok(true) => break,
+            Ok(false) => continue,
+            Err(error) => error,
+        };
+        let mut gap = Gap {
+            error,
+            block,
+            offset: base + reader.position(),
+            position: len,
+            resume: None,
+        };
+        let found = match resync {
+            true => find_block(v_in, block + 1, buf, &mut salvage.data, &mut len, strict),
+            false => None,
+        };
+        match found {
+            Some((offset, next, bfinal)) => {
+                gap.resume = Some(offset);
+                salvage.gaps.push(gap);
+                if bfinal {
+                    break;
+                }
+                base = offset / 8 * 8;
+                reader = next;
+            }
+            None => {
+                salvage.gaps.push(gap);
+                break;
+            }
+        }
+    }
+    salvage.data.truncate(len);
+    salvage
+}
@@ -0,0 +1,94 @@
+//! # Statistics
+//!
+//! What the encoder did with the input.
+//! - Literal and match counts.
+//! - Histograms of match lengths (indexed by length) and distances (indexed
+//!   by deflate's distance code).
+//! - Per block: type, header and payload sizes in bits, literals and matches.
+//! - Time spent in LZSS and in Huffman's coding.
+//!
+//! ## Examples
+//!
+//! ### Easy to use.
+//! ```
+//! use devker::prelude::{deflate_stats, inflate, BlockType, Cache};
+//!
+//! let mut cache = Cache::new();
+//! let v_in = "Hello world ! ".repeat(4).into_bytes();
+//!
+//! let (encoded, stats) = deflate_stats(&v_in, BlockType::Dynamic, &mut cache);
+//! assert_eq!(inflate(&encoded, &mut cache).unwrap(), v_in);
+//! assert_eq!(stats.literals, 16);
+//! assert_eq!(stats.matches, 1);
+//! assert_eq!(stats.lengths[40], 1);
+//! assert_eq!(stats.distances[7], 1);
+//! assert_eq!(stats.blocks.len(), 1);
+//! assert_eq!(stats.blocks[0].btype, BlockType::Dynamic);
+//! let block = &stats.blocks[0];
+//! assert_eq!((block.header_size + block.payload_size + 7) / 8, encoded.len());
+//! ```
+
+// Imports.
+use crate::code::Token;
+use crate::prelude::BlockType;
+use std::time::Duration;
+// Structures.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockStats {
+    pub btype: BlockType,
+    pub header_size: usize,
+    pub payload_size: usize,
+    pub literals: usize,
+    pub matches: usize,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub literals: usize,
+    pub matches: usize,
+    pub lengths: [usize; 259],
+    pub distances: [usize; 30],
+    pub blocks: Vec<BlockStats>,
+    pub lzss_time: Duration,
+    pub huffman_time: Duration,
+}
+// Implementations.
+impl BlockStats {
+    pub(crate) fn new(btype: BlockType) -> Self {
+        Self {
+            btype,
+            header_size: 0,
+            payload_size: 0,
+            literals: 0,
+            matches: 0,
+        }
+    }
+}
+impl Stats {
+    pub fn new() -> Self {
+        Self {
+            literals: 0,
+            matches: 0,
+            lengths: [0; 259],
+            distances: [0; 30],
+            blocks: Vec::new(),
+            lzss_time: Duration::default(),
+            huffman_time: Duration::default(),
+        }
+    }
+    pub(crate) fn count_tokens(&mut self, tokens: &[Token]) {
+        for token in tokens {
+            if token.is_pointer() {
+                self.matches += 1;
+                self.lengths[token.length() as usize + 3] += 1;
+                self.distances[token.distance_symbol()] += 1;
+            } else if *token != Token::END_OF_BLOCK {
+                self.literals += 1;
+            }
+        }
+    }
+}
+impl Default for Stats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
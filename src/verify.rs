@@ -0,0 +1,130 @@
+//! # Verify
+//!
+//! Check a deflate, zlib or gzip stream end to end without keeping its
+//! output. Only the last 32 KB, which matches may refer to, are kept.
+//!
+//! Returns the decompressed length and the checksum of the output, or the
+//! error the decoder would return.
+//! - Deflate: CRC-32 of the output.
+//! - Zlib: Adler-32 of the output, checked against the trailer.
+//! - Gzip: CRC-32 of the whole output, every member is checked against its
+//!   trailer.
+//!
+//! ## Examples
+//!
+//! ### Easy to use.
+//! ```
+//! use devker::prelude::{verify_zlib, zlib_encode, BlockType, Cache, InflateOptions};
+//!
+//! let mut cache = Cache::new();
+//! let options = InflateOptions::default();
+//! let v_in = "Hello world, this is a wonderful world !".repeat(1000).into_bytes();
+//! let encoded = zlib_encode(&v_in, BlockType::Dynamic, &mut cache);
+//!
+//! let verified = verify_zlib(&encoded, options, &mut cache).unwrap();
+//! assert_eq!(verified.len, v_in.len());
+//! assert_eq!(verified.checksum.to_be_bytes()[..], encoded[encoded.len() - 4..]);
+//! ```
+//!
+//! ### Corrupted stream.
+//! ```
+//! use devker::prelude::{gzip_encode, verify_gzip, BlockType, Cache, InflateOptions};
+//!
+//! let mut cache = Cache::new();
+//! let options = InflateOptions::default();
+//! let mut encoded = gzip_encode(b"Hello world !", BlockType::Raw, &mut cache);
+//! encoded[15] = b'h';
+//!
+//! assert_eq!(verify_gzip(&encoded, options, &mut cache).unwrap_err(), "Gzip checksum error");
+//! ```
+
+// Imports.
+use crate::adler32::Adler32;
+use crate::crc32::{crc32_combine, Crc32};
+use crate::deflate::inflate_window;
+use crate::prelude::{Cache, InflateOptions};
+use crate::{gzip, zlib};
+// Structures.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Verified {
+    pub len: usize,
+    pub checksum: u32,
+}
+// Main functions.
+pub fn verify_deflate(
+    v_in: &[u8],
+    options: InflateOptions,
+    cache: &mut Cache,
+) -> Result<Verified, String> {
+    // Variable initialization.
+    let strict = !options.permissive;
+    let mut crc32 = Crc32::new();
+
+    // Algorithm.
+    let (len, _) = inflate_window(v_in, &mut cache.buf, strict, |x| crc32.update(x))?;
+    Ok(Verified {
+        len,
+        checksum: u32::from_le_bytes(crc32.checksum()),
+    })
+}
+
+pub fn verify_zlib(
+    v_in: &[u8],
+    options: InflateOptions,
+    cache: &mut Cache,
+) -> Result<Verified, String> {
+    // Conditions.
+    if v_in.len() < zlib::HEADER_LEN + zlib::ADLER_LEN {
+        return Err(zlib::ERROR_LENGTH.into());
+    }
+    zlib::read_header(v_in)?;
+    // Variable initialization.
+    let data = &v_in[zlib::HEADER_LEN..v_in.len() - zlib::ADLER_LEN];
+    let strict = !options.permissive;
+    let mut adler32 = Adler32::new();
+
+    // Algorithm.
+    let (len, _) = inflate_window(data, &mut cache.buf, strict, |x| adler32.update(x))?;
+    zlib::check_checksum(&v_in[v_in.len() - zlib::ADLER_LEN..], adler32.checksum())?;
+    Ok(Verified {
+        len,
+        checksum: u32::from_be_bytes(adler32.checksum()),
+    })
+}
+
+pub fn verify_gzip(
+    v_in: &[u8],
+    options: InflateOptions,
+    cache: &mut Cache,
+) -> Result<Verified, String> {
+    // Variable initialization.
+    let strict = !options.permissive;
+    let buf = cache.inner_mut();
+    let mut verified = Verified {
+        len: 0,
+        checksum: 0,
+    };
+    let mut pos = 0;
+
+    // Algorithm.
+    loop {
+        let header_len = gzip::read_header(&v_in[pos..])?;
+        let mut crc32 = Crc32::new();
+        let (len, consumed) =
+            inflate_window(&v_in[pos + header_len..], buf, strict, |x| crc32.update(x))?;
+        pos += header_len + consumed;
+        let trailer = match v_in.get(pos..pos + gzip::TRAILER_LEN) {
+            Some(trailer) => trailer,
+            None => return Err(gzip::ERROR_TRAILER.into()),
+        };
+        gzip::check_checksum(trailer, crc32.checksum(), len)?;
+        let checksum = u32::from_le_bytes(crc32.checksum());
+        verified.checksum = crc32_combine(verified.checksum, checksum, len);
+        verified.len += len;
+        pos += gzip::TRAILER_LEN;
+        if pos == v_in.len() {
+            break;
+        }
+    }
+    Ok(verified)
+}
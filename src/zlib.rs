@@ -41,42 +41,42 @@
 //! let decoded = zlib_decode(&encoded, &mut cache).unwrap();
 //! assert_eq!(v_in, decoded);
 //! ```
+//!
+//! ### Caller's buffer.
+//! ```
+//! use devker::prelude::{deflate_bound, zlib_decode, zlib_encode_to, BlockType, Cache};
+//!
+//! let mut cache = Cache::new();
+//! let v_in = "Hello world, this is a wonderful world !".repeat(10).into_bytes();
+//!
+//! // Header and checksum take 6 bytes more than deflate.
+//! let mut buf = vec![0; deflate_bound(v_in.len(), BlockType::Dynamic) + 6];
+//! let len = zlib_encode_to(&v_in, BlockType::Dynamic, &mut cache, &mut buf).unwrap();
+//! assert_eq!(zlib_decode(&buf[..len], &mut cache).unwrap(), v_in);
+//! assert!(zlib_encode_to(&v_in, BlockType::Dynamic, &mut cache, &mut buf[..len - 1]).is_err());
+//! ```
 
 // Import.
 use crate::adler32::Adler32;
-use crate::prelude::{deflate, inflate, inflate_to, BlockType, Cache};
-use std::convert::TryInto;
+use crate::deflate::{copy_at, deflate_into, deflate_read, deflate_slice, write_all};
+use crate::deflate::{inflate_into, inflate_len};
+use crate::prelude::{Cache, DeflateOptions, InflateOptions};
+use crate::stats::Stats;
+use std::io::{Read, Write};
 // Constants.
 const ERROR_ADLER32: &str = "Zlib checksum error";
 const ERROR_DEFLATE: &str = "Zlib only supports deflate compression algorithm";
 const ERROR_DICT: &str = "Zlib dictionaries is not implemented";
 const ERROR_FCHECK: &str = "Fcheck must be multiple of 31";
-const ERROR_LENGTH: &str = "Zlib's header is missing";
-const HEADER_LEN: usize = 2;
-const ADLER_LEN: usize = 4;
+pub(crate) const ERROR_LENGTH: &str = "Zlib's header is missing";
+pub(crate) const HEADER_LEN: usize = 2;
+pub(crate) const ADLER_LEN: usize = 4;
 const METHOD_DEFLATE: u8 = 8;
-// Main functions.
-pub fn zlib_encode(v_in: &[u8], btype: BlockType, cache: &mut Cache) -> Vec<u8> {
-    // Variable initialization.
-    let cmf = 0x78;
-    let flevel = 2;
-    let fcheck = 28;
-    let flg = flevel << 6 | fcheck;
-    let mut adler32 = Adler32::new();
-
-    // Algorithm.
-    let mut data = deflate(v_in, btype, cache);
-    adler32.update(&v_in);
-    let mut v_out = Vec::with_capacity(data.len() + 2 + 4);
-    v_out.extend(&[cmf, flg]);
-    v_out.append(&mut data);
-    v_out.extend(&adler32.checksum());
-    v_out
-}
-
-pub fn zlib_decode(v_in: &[u8], cache: &mut Cache) -> Result<Vec<u8>, String> {
-    // Conditions.
-    if v_in.len() < HEADER_LEN + ADLER_LEN {
+// CMF: deflate with a 32K window. FLG: default level, no dictionary and FCHECK.
+const ZLIB_HEADER: [u8; 2] = [0x78, 0x9C];
+// Functions.
+pub(crate) fn read_header(v_in: &[u8]) -> Result<usize, String> {
+    if v_in.len() < HEADER_LEN {
         return Err(ERROR_LENGTH.into());
     }
     let cmf = v_in[0];
@@ -84,50 +84,141 @@ pub fn zlib_decode(v_in: &[u8], cache: &mut Cache) -> Result<Vec<u8>, String> {
     if (cmf & 0x0F) != METHOD_DEFLATE {
         return Err(ERROR_DEFLATE.into());
     }
-    if ((cmf as u16) << 8 | flg as u16) % 31 != 0 {
+    if !((cmf as u16) << 8 | flg as u16).is_multiple_of(31) {
         return Err(ERROR_FCHECK.into());
     }
     if (flg & 0b100_000) > 0 {
         return Err(ERROR_DICT.into());
     }
+    Ok(HEADER_LEN)
+}
+pub(crate) fn check_checksum(trailer: &[u8], checksum: [u8; 4]) -> Result<(), String> {
+    if checksum[..] != trailer[..] {
+        return Err(ERROR_ADLER32.into());
+    }
+    Ok(())
+}
+fn encode_into(
+    v_in: &[u8],
+    options: DeflateOptions,
+    cache: &mut Cache,
+    v_out: &mut Vec<u8>,
+    stats: Option<&mut Stats>,
+) {
+    let mut adler32 = Adler32::new();
+    v_out.extend(&ZLIB_HEADER);
+    deflate_into(v_in, options, cache, v_out, stats, |x| adler32.update(x));
+    v_out.extend(&adler32.checksum());
+}
+// Main functions.
+pub fn zlib_encode(v_in: &[u8], options: impl Into<DeflateOptions>, cache: &mut Cache) -> Vec<u8> {
+    let mut v_out = Vec::new();
+    zlib_encode_append(v_in, options, cache, &mut v_out);
+    v_out
+}
+
+pub fn zlib_encode_append(
+    v_in: &[u8],
+    options: impl Into<DeflateOptions>,
+    cache: &mut Cache,
+    v_out: &mut Vec<u8>,
+) {
+    encode_into(v_in, options.into(), cache, v_out, None);
+}
+
+pub fn zlib_encode_to(
+    v_in: &[u8],
+    options: impl Into<DeflateOptions>,
+    cache: &mut Cache,
+    v_out: &mut [u8],
+) -> Result<usize, String> {
     // Variable initialization.
     let mut adler32 = Adler32::new();
+    let mut pos = 0;
 
     // Algorithm.
-    let v_out = inflate(&v_in[HEADER_LEN..v_in.len() - ADLER_LEN], cache)?;
-    adler32.update(&v_out);
-    let _adler32: [u8; 4] = v_in[v_in.len() - ADLER_LEN..].try_into().unwrap();
-    if adler32.checksum() != _adler32 {
-        return Err(ERROR_ADLER32.into());
+    copy_at(&ZLIB_HEADER, v_out, &mut pos)?;
+    let update = |x: &[u8]| adler32.update(x);
+    deflate_slice(v_in, options.into(), cache, v_out, &mut pos, update)?;
+    copy_at(&adler32.checksum(), v_out, &mut pos)?;
+    Ok(pos)
+}
+
+pub fn zlib_encode_stats(
+    v_in: &[u8],
+    options: impl Into<DeflateOptions>,
+    cache: &mut Cache,
+) -> (Vec<u8>, Stats) {
+    // Variable initialization.
+    let mut v_out = Vec::new();
+    let mut stats = Stats::new();
+
+    // Algorithm.
+    encode_into(v_in, options.into(), cache, &mut v_out, Some(&mut stats));
+    (v_out, stats)
+}
+
+pub fn zlib_encode_stream(
+    mut reader: impl Read,
+    mut writer: impl Write,
+    options: impl Into<DeflateOptions>,
+    cache: &mut Cache,
+) -> Result<usize, String> {
+    // Variable initialization.
+    let mut adler32 = Adler32::new();
+
+    // Algorithm.
+    write_all(&mut writer, &ZLIB_HEADER)?;
+    let len = deflate_read(&mut reader, &mut writer, options.into(), cache, |x| {
+        adler32.update(x)
+    })?;
+    write_all(&mut writer, &adler32.checksum())?;
+    Ok(len)
+}
+
+pub fn zlib_decode(v_in: &[u8], cache: &mut Cache) -> Result<Vec<u8>, String> {
+    zlib_decode_with(v_in, InflateOptions::default(), cache)
+}
+
+pub fn zlib_decode_with(
+    v_in: &[u8],
+    options: InflateOptions,
+    cache: &mut Cache,
+) -> Result<Vec<u8>, String> {
+    // Conditions.
+    if v_in.len() < HEADER_LEN + ADLER_LEN {
+        return Err(ERROR_LENGTH.into());
     }
+    read_header(v_in)?;
+    // Algorithm.
+    let mut adler32 = Adler32::new();
+    let data = &v_in[HEADER_LEN..v_in.len() - ADLER_LEN];
+    let (v_out, _) = inflate_len(data, options, cache, |x| adler32.update(x))?;
+    check_checksum(&v_in[v_in.len() - ADLER_LEN..], adler32.checksum())?;
     Ok(v_out)
 }
 
 pub fn zlib_decode_to(v_in: &[u8], cache: &mut Cache, v_out: &mut [u8]) -> Result<(), String> {
+    zlib_decode_to_with(v_in, InflateOptions::default(), cache, v_out)
+}
+
+pub fn zlib_decode_to_with(
+    v_in: &[u8],
+    options: InflateOptions,
+    cache: &mut Cache,
+    v_out: &mut [u8],
+) -> Result<(), String> {
     // Conditions.
     if v_in.len() < HEADER_LEN + ADLER_LEN {
         return Err(ERROR_LENGTH.into());
     }
-    let cmf = v_in[0];
-    let flg = v_in[1];
-    if (cmf & 0x0F) != METHOD_DEFLATE {
-        return Err(ERROR_DEFLATE.into());
-    }
-    if ((cmf as u16) << 8 | flg as u16) % 31 != 0 {
-        return Err(ERROR_FCHECK.into());
-    }
-    if (flg & 0b100_000) > 0 {
-        return Err(ERROR_DICT.into());
-    }
-    // Variable initialization.
-    let mut adler32 = Adler32::new();
-
+    read_header(v_in)?;
     // Algorithm.
-    inflate_to(&v_in[HEADER_LEN..v_in.len() - ADLER_LEN], cache, v_out)?;
-    adler32.update(v_out);
-    let _adler32: [u8; 4] = v_in[v_in.len() - ADLER_LEN..].try_into().unwrap();
-    if adler32.checksum() != _adler32 {
-        return Err(ERROR_ADLER32.into());
-    }
+    let mut adler32 = Adler32::new();
+    let data = &v_in[HEADER_LEN..v_in.len() - ADLER_LEN];
+    let (len, _) = inflate_into(data, options, cache, v_out, |x| adler32.update(x))?;
+    // The checksum covers the whole buffer, which the data is expected to fill.
+    adler32.update(&v_out[len..]);
+    check_checksum(&v_in[v_in.len() - ADLER_LEN..], adler32.checksum())?;
     Ok(())
 }